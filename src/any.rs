@@ -0,0 +1,133 @@
+use crate::{error::Error, mapper, Database};
+use rocksdb::{ColumnFamilyDescriptor, Options};
+use std::path::Path;
+
+/// A database whose writeability is chosen at runtime.
+///
+/// Writes are only available through the `Writeable` variant.
+#[derive(Debug)]
+pub enum AnyDatabase<C, B> {
+    ReadOnly(Database<false, C, B>),
+    Writeable(Database<true, C, B>),
+}
+
+impl<'de, C: serde::de::Deserialize<'de>, B: serde::de::Deserialize<'de>> AnyDatabase<C, B> {
+    /// Open a database for reading only, or for writing with optimistic transactions.
+    pub fn open_any<P: AsRef<Path>>(
+        path: P,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        options: Options,
+        writeable: bool,
+    ) -> Result<Self, Error> {
+        if writeable {
+            Database::open(path, cfs, options).map(Self::Writeable)
+        } else {
+            Database::open(path, cfs, options).map(Self::ReadOnly)
+        }
+    }
+
+    pub fn read_config(&self) -> Result<C, mapper::Error> {
+        match self {
+            Self::ReadOnly(database) => database.read_config(),
+            Self::Writeable(database) => database.read_config(),
+        }
+    }
+
+    pub fn read_books(&self) -> Result<B, mapper::Error> {
+        match self {
+            Self::ReadOnly(database) => database.read_books(),
+            Self::Writeable(database) => database.read_books(),
+        }
+    }
+}
+
+impl<C, B> AnyDatabase<C, B> {
+    pub fn is_writeable(&self) -> bool {
+        matches!(self, Self::Writeable(_))
+    }
+
+    /// The config read when the database was opened.
+    pub fn config(&self) -> &C {
+        match self {
+            Self::ReadOnly(database) => &database.config,
+            Self::Writeable(database) => &database.config,
+        }
+    }
+
+    /// The books read when the database was opened.
+    pub fn books(&self) -> &B {
+        match self {
+            Self::ReadOnly(database) => &database.books,
+            Self::Writeable(database) => &database.books,
+        }
+    }
+
+    pub fn as_writeable(&self) -> Option<&Database<true, C, B>> {
+        match self {
+            Self::ReadOnly(_) => None,
+            Self::Writeable(database) => Some(database),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AnyDatabase;
+
+    #[derive(Clone, Debug, Eq, PartialEq, serde_derive::Deserialize, serde_derive::Serialize)]
+    struct Config {
+        name: String,
+        limit: u64,
+    }
+
+    #[test]
+    fn open_any() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            name: "foo".to_string(),
+            limit: 10,
+        };
+
+        crate::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            (),
+        )
+        .unwrap()
+        .db
+        .close();
+
+        let read_only_db =
+            AnyDatabase::<Config, ()>::open_any(&test_db_dir, vec![], Default::default(), false)
+                .unwrap();
+
+        assert!(!read_only_db.is_writeable());
+        assert!(read_only_db.as_writeable().is_none());
+        assert_eq!(read_only_db.read_config().unwrap(), config);
+
+        drop(read_only_db);
+
+        let writeable_db =
+            AnyDatabase::<Config, ()>::open_any(&test_db_dir, vec![], Default::default(), true)
+                .unwrap();
+
+        let new_config = Config {
+            name: "bar".to_string(),
+            limit: 20,
+        };
+
+        assert!(writeable_db.is_writeable());
+        assert_eq!(writeable_db.config(), &config);
+
+        writeable_db
+            .as_writeable()
+            .unwrap()
+            .write_config(&new_config)
+            .unwrap();
+
+        assert_eq!(writeable_db.read_config().unwrap(), new_config);
+    }
+}
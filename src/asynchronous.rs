@@ -0,0 +1,95 @@
+use crate::{mapper, Database};
+
+/// Run a blocking function on Tokio's blocking thread pool, propagating any panic.
+async fn spawn_blocking<T: Send + 'static, F: FnOnce() -> T + Send + 'static>(f: F) -> T {
+    match tokio::task::spawn_blocking(f).await {
+        Ok(value) => value,
+        Err(error) => match error.try_into_panic() {
+            Ok(panic) => std::panic::resume_unwind(panic),
+            Err(error) => panic!("Blocking database task failed: {error}"),
+        },
+    }
+}
+
+impl<const W: bool, C, B> Database<W, C, B>
+where
+    C: serde::de::DeserializeOwned + Send + 'static,
+    B: serde::de::DeserializeOwned + Send + 'static,
+{
+    /// Read the config on Tokio's blocking thread pool.
+    pub async fn read_config_async(&self) -> Result<C, mapper::Error> {
+        let db = self.db.clone();
+
+        spawn_blocking(move || Self::read_config_with_db(&db)).await
+    }
+
+    /// Read the books on Tokio's blocking thread pool.
+    pub async fn read_books_async(&self) -> Result<B, mapper::Error> {
+        let db = self.db.clone();
+
+        spawn_blocking(move || Self::read_books_with_db(&db)).await
+    }
+}
+
+impl<C, B> Database<true, C, B>
+where
+    C: serde::ser::Serialize + Send + 'static,
+    B: serde::ser::Serialize + Send + 'static,
+{
+    /// Write the config on Tokio's blocking thread pool.
+    pub async fn write_config_async(&self, config: C) -> Result<(), mapper::Error> {
+        let db = self.db.clone();
+
+        spawn_blocking(move || Self::write_config_with_db(&db, &config)).await
+    }
+
+    /// Write the books on Tokio's blocking thread pool.
+    pub async fn write_books_async(&self, books: B) -> Result<(), mapper::Error> {
+        let db = self.db.clone();
+
+        spawn_blocking(move || Self::write_books_with_db(&db, &books)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[derive(Clone, Debug, Eq, PartialEq, serde_derive::Deserialize, serde_derive::Serialize)]
+    struct Config {
+        name: String,
+        limit: u64,
+    }
+
+    #[tokio::test]
+    async fn round_trip_async() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            name: "foo".to_string(),
+            limit: 10,
+        };
+
+        let writeable_db = crate::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            (),
+        )
+        .unwrap();
+
+        assert_eq!(writeable_db.read_config_async().await.unwrap(), config);
+
+        let new_config = Config {
+            name: "bar".to_string(),
+            limit: 20,
+        };
+
+        writeable_db
+            .write_config_async(new_config.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(writeable_db.read_config_async().await.unwrap(), new_config);
+        assert_eq!(writeable_db.read_config().unwrap(), new_config);
+    }
+}
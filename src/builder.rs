@@ -0,0 +1,501 @@
+use crate::{
+    error::Error, merge_operator_cfs, set_ttls, Database, Endianness, MergeFunction, Metadata,
+    OpenMode, WriteMode, IN_MEMORY_DIR_NAME,
+};
+use rocksdb::{ColumnFamilyDescriptor, Options, TransactionDBOptions};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Builder for creating or opening a writeable database with named settings.
+///
+/// The config and books default to `()`, and setting them changes the builder's types. When
+/// opening, the stored values are read and the values set on the builder are ignored.
+pub struct DatabaseBuilder<C = (), B = ()> {
+    settings: Settings,
+    config: C,
+    books: B,
+}
+
+/// The settings that do not depend on the config and books types.
+#[derive(Default)]
+struct Settings {
+    path: PathBuf,
+    cfs: Vec<ColumnFamilyDescriptor>,
+    options: Options,
+    mode: OpenMode,
+    transaction_db_options: Option<TransactionDBOptions>,
+    ttls: Option<Vec<(String, i32)>>,
+    merge_operators: Vec<(String, MergeFunction)>,
+    endianness: Endianness,
+    schema_version: Option<u32>,
+    paranoid_checks: bool,
+    timeout: Option<Duration>,
+    in_memory: bool,
+}
+
+impl Settings {
+    /// Apply the column family settings, returning the options, descriptors, and write mode.
+    fn prepare(&mut self) -> (Options, Vec<ColumnFamilyDescriptor>, WriteMode) {
+        let mut options = std::mem::take(&mut self.options);
+
+        if self.paranoid_checks {
+            options.set_paranoid_checks(true);
+        }
+
+        let merge_operators = self
+            .merge_operators
+            .iter()
+            .map(|(cf_name, merge_function)| (cf_name.as_str(), *merge_function))
+            .collect::<Vec<_>>();
+        let mut cfs = merge_operator_cfs(std::mem::take(&mut self.cfs), &merge_operators);
+
+        let mode = match &self.ttls {
+            Some(ttls) => {
+                let ttls = ttls
+                    .iter()
+                    .map(|(cf_name, seconds)| (cf_name.as_str(), *seconds))
+                    .collect::<Vec<_>>();
+                set_ttls(&mut cfs, &ttls);
+
+                WriteMode::PlainWithTtl
+            }
+            None => match self.mode {
+                OpenMode::PessimisticTransactions => WriteMode::PessimisticTransactions(
+                    self.transaction_db_options.take().unwrap_or_default(),
+                ),
+                mode => mode.into(),
+            },
+        };
+
+        (options, cfs, mode)
+    }
+
+    fn register_merge_operators<C, B>(&self, database: &Database<true, C, B>) {
+        for (cf_name, _) in &self.merge_operators {
+            database.db.register_merge_operator(cf_name);
+        }
+    }
+
+    fn open<OC, OB>(mut self) -> Result<Database<true, OC, OB>, Error>
+    where
+        OC: serde::de::DeserializeOwned,
+        OB: serde::de::DeserializeOwned,
+    {
+        let (options, cfs, mode) = self.prepare();
+        let database = Database::open_checked(
+            std::mem::take(&mut self.path),
+            cfs,
+            options,
+            mode,
+            self.schema_version,
+        )?;
+
+        self.register_merge_operators(&database);
+
+        Ok(database)
+    }
+}
+
+impl DatabaseBuilder {
+    pub fn new() -> Self {
+        Self {
+            settings: Settings::default(),
+            config: (),
+            books: (),
+        }
+    }
+}
+
+impl Default for DatabaseBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C, B> DatabaseBuilder<C, B> {
+    pub fn path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.settings.path = path.into();
+        self
+    }
+
+    /// Column families in addition to the managed tables, which may also include descriptors for
+    /// the managed tables to configure their options.
+    pub fn column_families(mut self, cfs: Vec<ColumnFamilyDescriptor>) -> Self {
+        self.settings.cfs = cfs;
+        self
+    }
+
+    pub fn options(mut self, options: Options) -> Self {
+        self.settings.options = options;
+        self
+    }
+
+    /// Use the given kind of transactions (optimistic by default).
+    pub fn mode(mut self, mode: OpenMode) -> Self {
+        self.settings.mode = mode;
+        self
+    }
+
+    pub fn optimistic(self) -> Self {
+        self.mode(OpenMode::OptimisticTransactions)
+    }
+
+    pub fn pessimistic(self) -> Self {
+        self.mode(OpenMode::PessimisticTransactions)
+    }
+
+    pub fn without_transactions(self) -> Self {
+        self.mode(OpenMode::WithoutTransactions)
+    }
+
+    /// Use pessimistic transactions that are configured with the given options (for example lock
+    /// timeouts).
+    pub fn transaction_db_options(mut self, transaction_db_options: TransactionDBOptions) -> Self {
+        self.settings.transaction_db_options = Some(transaction_db_options);
+        self.pessimistic()
+    }
+
+    /// Expire entries in the named column family after the given number of seconds, where
+    /// non-positive values disable expiry.
+    ///
+    /// Setting any TTL opens the database without transactions, overriding the mode. A database
+    /// created with TTLs must always be opened with them (although the values may differ).
+    pub fn ttl(mut self, cf_name: &str, seconds: i32) -> Self {
+        self.settings
+            .ttls
+            .get_or_insert_with(Vec::new)
+            .push((cf_name.to_string(), seconds));
+        self
+    }
+
+    /// Use an associative merge operator for the named column family, which may be one of the
+    /// managed tables.
+    ///
+    /// The column family is created with default options and should not also be included in the
+    /// column families, and the merge operator must be provided every time the database is opened.
+    pub fn merge_operator(mut self, cf_name: &str, merge_function: MergeFunction) -> Self {
+        self.settings
+            .merge_operators
+            .push((cf_name.to_string(), merge_function));
+        self
+    }
+
+    /// The endianness used to encode config and books values when creating the database.
+    ///
+    /// When opening, the recorded endianness is always used.
+    pub fn endianness(mut self, endianness: Endianness) -> Self {
+        self.settings.endianness = endianness;
+        self
+    }
+
+    /// The schema version to record when creating the database, or to require when opening it.
+    ///
+    /// Opening fails with [`Error::SchemaMismatch`] if the recorded version differs.
+    pub fn schema_version(mut self, schema_version: u32) -> Self {
+        self.settings.schema_version = Some(schema_version);
+        self
+    }
+
+    /// Enable RocksDB's paranoid checks, so that detected corruption causes the open to fail.
+    pub fn paranoid_checks(mut self, paranoid_checks: bool) -> Self {
+        self.settings.paranoid_checks = paranoid_checks;
+        self
+    }
+
+    /// Fail with [`Error::OpenTimedOut`] if opening takes longer than the given timeout.
+    ///
+    /// See [`Database::open_with_timeout`] for the caveats. This has no effect when creating.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.settings.timeout = Some(timeout);
+        self
+    }
+
+    /// Create the database in RocksDB's in-memory environment, ignoring the path.
+    ///
+    /// The contents are discarded when the last handle to the database is dropped. This only
+    /// applies when creating.
+    pub fn in_memory(mut self) -> Self {
+        self.settings.in_memory = true;
+        self
+    }
+
+    /// The config to write when creating the database.
+    pub fn config<NC>(self, config: NC) -> DatabaseBuilder<NC, B> {
+        DatabaseBuilder {
+            settings: self.settings,
+            config,
+            books: self.books,
+        }
+    }
+
+    /// The books to write when creating the database.
+    pub fn books<NB>(self, books: NB) -> DatabaseBuilder<C, NB> {
+        DatabaseBuilder {
+            settings: self.settings,
+            config: self.config,
+            books,
+        }
+    }
+
+    /// Open an existing database, reading its stored config and books.
+    pub fn open<OC, OB>(self) -> Result<Database<true, OC, OB>, Error>
+    where
+        OC: serde::de::DeserializeOwned + Send + 'static,
+        OB: serde::de::DeserializeOwned + Send + 'static,
+    {
+        let settings = self.settings;
+
+        match settings.timeout {
+            Some(timeout) => {
+                let (sender, receiver) = std::sync::mpsc::channel();
+
+                std::thread::spawn(move || {
+                    // The receiver will have been dropped if we have timed out.
+                    let _ = sender.send(settings.open());
+                });
+
+                receiver
+                    .recv_timeout(timeout)
+                    .map_err(|_| Error::OpenTimedOut)?
+            }
+            None => settings.open(),
+        }
+    }
+}
+
+impl<C: serde::ser::Serialize, B: serde::ser::Serialize> DatabaseBuilder<C, B> {
+    /// Create the database, writing the config and books.
+    pub fn create(mut self) -> Result<Database<true, C, B>, Error> {
+        let (mut options, cfs, mode) = self.settings.prepare();
+        let metadata = Metadata {
+            endianness: self.settings.endianness,
+            schema_version: self.settings.schema_version,
+        };
+
+        let path = if self.settings.in_memory {
+            options.set_env(&rocksdb::Env::mem_env()?);
+
+            std::env::temp_dir().join(IN_MEMORY_DIR_NAME)
+        } else {
+            std::mem::take(&mut self.settings.path)
+        };
+
+        let database =
+            Database::create_internal(&path, cfs, options, mode, metadata, self.config, self.books);
+
+        if self.settings.in_memory {
+            // The Rust bindings create an empty directory at the path even though it is unused.
+            let _ = std::fs::remove_dir(path);
+        }
+
+        let database = database?;
+        self.settings.register_merge_operators(&database);
+
+        Ok(database)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DatabaseBuilder;
+
+    #[derive(Clone, Debug, Eq, PartialEq, serde_derive::Deserialize, serde_derive::Serialize)]
+    struct Config {
+        name: String,
+        limit: u64,
+    }
+
+    #[test]
+    fn create_and_open() {
+        for mode in [
+            crate::OpenMode::OptimisticTransactions,
+            crate::OpenMode::PessimisticTransactions,
+        ] {
+            let test_db_dir = tempfile::tempdir().unwrap();
+            let config = Config {
+                name: "foo".to_string(),
+                limit: 10,
+            };
+
+            let builder = DatabaseBuilder::new().path(test_db_dir.path());
+            let builder = match mode {
+                crate::OpenMode::PessimisticTransactions => builder.pessimistic(),
+                _ => builder.optimistic(),
+            };
+
+            let writeable_db = builder.config(config.clone()).create().unwrap();
+
+            assert_eq!(writeable_db.read_config().unwrap(), config);
+            assert_eq!(
+                writeable_db.db.as_pessimistic().is_some(),
+                mode == crate::OpenMode::PessimisticTransactions
+            );
+
+            writeable_db.db.close();
+
+            let writeable_db = DatabaseBuilder::new()
+                .path(test_db_dir.path())
+                .mode(mode)
+                .open::<Config, ()>()
+                .unwrap();
+
+            assert_eq!(writeable_db.config, config);
+        }
+    }
+
+    fn add_u64(
+        _key: &[u8],
+        existing: Option<&[u8]>,
+        operands: &rocksdb::MergeOperands,
+    ) -> Option<Vec<u8>> {
+        let existing = existing.map_or(0, |bytes| u64::from_be_bytes(bytes.try_into().unwrap()));
+
+        Some(
+            operands
+                .into_iter()
+                .fold(existing, |total, operand| {
+                    total + u64::from_be_bytes(operand.try_into().unwrap())
+                })
+                .to_be_bytes()
+                .to_vec(),
+        )
+    }
+
+    #[test]
+    fn settings() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            name: "foo".to_string(),
+            limit: 10,
+        };
+
+        let writeable_db = DatabaseBuilder::new()
+            .path(test_db_dir.path())
+            .transaction_db_options(Default::default())
+            .endianness(crate::Endianness::Little)
+            .schema_version(2)
+            .merge_operator("counters", add_u64)
+            .paranoid_checks(true)
+            .config(config.clone())
+            .create()
+            .unwrap();
+
+        let cf = writeable_db.db.handle("counters").unwrap();
+        writeable_db
+            .db
+            .merge(cf, b"count", 1u64.to_be_bytes())
+            .unwrap();
+
+        assert!(writeable_db.db.as_pessimistic().is_some());
+        assert_eq!(writeable_db.db.endianness(), crate::Endianness::Little);
+        assert_eq!(writeable_db.schema_version().unwrap(), 2);
+
+        writeable_db.db.close();
+
+        let open = |schema_version| {
+            DatabaseBuilder::new()
+                .path(test_db_dir.path())
+                .pessimistic()
+                .schema_version(schema_version)
+                .merge_operator("counters", add_u64)
+                .paranoid_checks(true)
+                .timeout(std::time::Duration::from_secs(60))
+                .open::<Config, ()>()
+        };
+
+        assert!(matches!(
+            open(3),
+            Err(crate::error::Error::SchemaMismatch {
+                expected: 3,
+                found: 2
+            })
+        ));
+
+        let writeable_db = open(2).unwrap();
+        let cf = writeable_db.db.handle("counters").unwrap();
+        writeable_db
+            .db
+            .merge(cf, b"count", 2u64.to_be_bytes())
+            .unwrap();
+
+        assert_eq!(writeable_db.config, config);
+        assert_eq!(writeable_db.db.endianness(), crate::Endianness::Little);
+        assert_eq!(
+            &*writeable_db.db.get(cf, b"count").unwrap().unwrap(),
+            3u64.to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn paranoid_checks() {
+        for pessimistic in [false, true] {
+            let test_db_dir = tempfile::tempdir().unwrap();
+            let config = Config {
+                name: "foo".to_string(),
+                limit: 10,
+            };
+
+            let builder = || {
+                let builder = DatabaseBuilder::new()
+                    .path(test_db_dir.path())
+                    .paranoid_checks(true);
+
+                if pessimistic {
+                    builder.pessimistic()
+                } else {
+                    builder.optimistic()
+                }
+            };
+
+            let writeable_db = builder().config(config.clone()).create().unwrap();
+
+            assert_eq!(writeable_db.db.as_pessimistic().is_some(), pessimistic);
+
+            writeable_db.db.close();
+
+            let writeable_db = builder().open::<Config, ()>().unwrap();
+
+            assert_eq!(writeable_db.db.as_pessimistic().is_some(), pessimistic);
+            assert_eq!(writeable_db.config, config);
+        }
+    }
+
+    #[test]
+    fn ttl_and_in_memory() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let test_cf = rocksdb::ColumnFamilyDescriptor::new("test", Default::default());
+
+        let writeable_db = DatabaseBuilder::new()
+            .path(test_db_dir.path())
+            .column_families(vec![test_cf])
+            .ttl("test", 60)
+            .create()
+            .unwrap();
+
+        assert!(writeable_db.db.as_optimistic().is_none());
+        assert!(writeable_db.db.as_pessimistic().is_none());
+
+        writeable_db.db.close();
+
+        let test_cf = rocksdb::ColumnFamilyDescriptor::new("test", Default::default());
+
+        DatabaseBuilder::new()
+            .path(test_db_dir.path())
+            .column_families(vec![test_cf])
+            .ttl("test", 60)
+            .open::<(), ()>()
+            .unwrap();
+
+        let config = Config {
+            name: "foo".to_string(),
+            limit: 10,
+        };
+        let in_memory_db = DatabaseBuilder::new()
+            .in_memory()
+            .config(config.clone())
+            .create()
+            .unwrap();
+
+        assert_eq!(in_memory_db.read_config().unwrap(), config);
+    }
+}
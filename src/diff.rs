@@ -0,0 +1,59 @@
+use crate::wrapper::KeyValuePair;
+use std::collections::BTreeMap;
+
+/// Key-by-key differences between the contents of a column family in two databases.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TableDiff {
+    pub only_in_self: Vec<Box<[u8]>>,
+    pub only_in_other: Vec<Box<[u8]>>,
+    pub different: Vec<Box<[u8]>>,
+}
+
+impl TableDiff {
+    pub fn is_empty(&self) -> bool {
+        self.only_in_self.is_empty() && self.only_in_other.is_empty() && self.different.is_empty()
+    }
+
+    pub(super) fn new<
+        I: Iterator<Item = Result<KeyValuePair, rocksdb::Error>>,
+        J: Iterator<Item = Result<KeyValuePair, rocksdb::Error>>,
+    >(
+        entries: I,
+        other_entries: J,
+    ) -> Result<Self, rocksdb::Error> {
+        let mut other_entries = other_entries.collect::<Result<BTreeMap<_, _>, _>>()?;
+        let mut diff = Self::default();
+
+        for result in entries {
+            let (key, value) = result?;
+
+            match other_entries.remove(&key) {
+                Some(other_value) => {
+                    if value != other_value {
+                        diff.different.push(key);
+                    }
+                }
+                None => {
+                    diff.only_in_self.push(key);
+                }
+            }
+        }
+
+        diff.only_in_other = other_entries.into_keys().collect();
+
+        Ok(diff)
+    }
+}
+
+/// Differences between the managed tables of two databases.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DiffReport {
+    pub config: TableDiff,
+    pub books: TableDiff,
+}
+
+impl DiffReport {
+    pub fn is_empty(&self) -> bool {
+        self.config.is_empty() && self.books.is_empty()
+    }
+}
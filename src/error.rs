@@ -6,6 +6,27 @@ pub enum Error {
     InvalidKey(Vec<u8>),
     #[error("Invalid value bytes")]
     InvalidValue(Vec<u8>),
+    #[error("No merge operator configured for column family")]
+    NoMergeOperator,
+    #[error("Schema version mismatch (expected {expected}, found {found})")]
+    SchemaMismatch { expected: u32, found: u32 },
+    #[error("Checkpoint target {} already exists", path.display())]
+    CheckpointTargetExists { path: std::path::PathBuf },
+    #[error("Unknown column family {0}")]
+    UnknownColumnFamily(String),
+    #[error("Database directory {} is not writable", path.display())]
+    NotWritable { path: std::path::PathBuf },
+    #[error("Write would block on a write stall")]
+    WouldBlock,
+    #[error("Operation not supported for this database type")]
+    Unsupported,
+    #[error("Page limit must be positive")]
+    ZeroPageLimit,
+    #[error("Opening the database timed out")]
+    OpenTimedOut,
+    #[cfg(feature = "serde_json")]
+    #[error("JSON error")]
+    Json(#[from] serde_json::Error),
     #[error("Mapper error")]
     Mapper(#[from] crate::mapper::Error),
 }
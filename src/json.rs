@@ -0,0 +1,140 @@
+use crate::{error::Error, mapper, Database};
+
+const CONFIG_FIELD_NAME: &str = "config";
+const BOOKS_FIELD_NAME: &str = "books";
+
+impl<const W: bool, C, B> Database<W, C, B>
+where
+    C: serde::ser::Serialize + serde::de::DeserializeOwned,
+    B: serde::ser::Serialize + serde::de::DeserializeOwned,
+{
+    /// Export the stored config and books as a JSON object with `config` and `books` fields.
+    pub fn export_json(&self) -> Result<serde_json::Value, Error> {
+        let mut value = serde_json::Map::new();
+
+        value.insert(
+            CONFIG_FIELD_NAME.to_string(),
+            serde_json::to_value(self.read_config()?)?,
+        );
+        value.insert(
+            BOOKS_FIELD_NAME.to_string(),
+            serde_json::to_value(self.read_books()?)?,
+        );
+
+        Ok(value.into())
+    }
+}
+
+impl<C, B> Database<true, C, B>
+where
+    C: serde::ser::Serialize + serde::de::DeserializeOwned,
+    B: serde::ser::Serialize + serde::de::DeserializeOwned,
+{
+    /// Write the config and books from a JSON object in the format produced by
+    /// [`Database::export_json`] in a single transaction.
+    pub fn import_json(&self, value: serde_json::Value) -> Result<(), Error> {
+        let mut value = match value {
+            serde_json::Value::Object(value) => value,
+            other => {
+                return Err(<serde_json::Error as serde::de::Error>::invalid_type(
+                    serde::de::Unexpected::Other(&other.to_string()),
+                    &"a JSON object",
+                )
+                .into())
+            }
+        };
+
+        let mut field = |name| {
+            value
+                .remove(name)
+                .ok_or_else(|| <serde_json::Error as serde::de::Error>::missing_field(name))
+        };
+
+        let config: C = serde_json::from_value(field(CONFIG_FIELD_NAME)?)?;
+        let books: B = serde_json::from_value(field(BOOKS_FIELD_NAME)?)?;
+
+        let _guard = self.db.write_guard();
+        // Safe because we know the wrapper is writeable.
+        let tx = self.transaction().unwrap();
+
+        self.write_config_in(&tx, &config)?;
+        self.write_books_in(&tx, &books)?;
+
+        tx.commit().map_err(mapper::Error::from)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[derive(Clone, Debug, Eq, PartialEq, serde_derive::Deserialize, serde_derive::Serialize)]
+    struct Config {
+        name: String,
+        limit: u64,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, serde_derive::Deserialize, serde_derive::Serialize)]
+    struct Books {
+        last_scrape_ms: u64,
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            name: "foo".to_string(),
+            limit: 10,
+        };
+        let books = Books { last_scrape_ms: 1 };
+
+        let writeable_db = crate::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        let exported = writeable_db.export_json().unwrap();
+
+        assert_eq!(
+            exported,
+            serde_json::json!({
+                "config": { "name": "foo", "limit": 10 },
+                "books": { "last_scrape_ms": 1 }
+            })
+        );
+
+        writeable_db
+            .import_json(serde_json::json!({
+                "config": { "name": "bar", "limit": 20 },
+                "books": { "last_scrape_ms": 2 }
+            }))
+            .unwrap();
+
+        assert_eq!(
+            writeable_db.read_config().unwrap(),
+            Config {
+                name: "bar".to_string(),
+                limit: 20
+            }
+        );
+        assert_eq!(
+            writeable_db.read_books().unwrap(),
+            Books { last_scrape_ms: 2 }
+        );
+
+        writeable_db.import_json(exported.clone()).unwrap();
+
+        assert_eq!(writeable_db.export_json().unwrap(), exported);
+        assert!(matches!(
+            writeable_db
+                .import_json(serde_json::json!({ "config": { "name": "baz", "limit": 0 } })),
+            Err(crate::error::Error::Json(_))
+        ));
+        assert_eq!(writeable_db.read_config().unwrap(), config);
+    }
+}
@@ -1,27 +1,329 @@
 use mapper::TableMapper;
 use rocksdb::{
-    ColumnFamily, ColumnFamilyDescriptor, OptimisticTransactionDB, Options, TransactionDB,
-    TransactionDBOptions, DB,
+    ColumnFamily, ColumnFamilyDescriptor, ColumnFamilyTtl, IteratorMode, OptimisticTransactionDB,
+    Options, TransactionDB, TransactionDBOptions, WriteOptions, DB,
 };
 
+use std::collections::HashMap;
 use std::path::Path;
+use std::time::Duration;
 
+/// Iterate over the entries of a managed table in key order, skipping reserved keys.
+pub(crate) fn field_entries<'a>(
+    db: &'a Db,
+    cf: &ColumnFamily,
+) -> impl Iterator<Item = Result<KeyValuePair, rocksdb::Error>> + use<'a> {
+    db.iterator(cf, IteratorMode::Start).filter(|result| {
+        result
+            .as_ref()
+            .map_or(true, |(key, _)| !is_reserved_key(key))
+    })
+}
+
+fn is_reserved_key(key: &[u8]) -> bool {
+    RESERVED_KEYS
+        .iter()
+        .any(|reserved| reserved.as_bytes() == key)
+}
+
+/// Evaluate an expression with the standard Bincode configuration for the given endianness.
+macro_rules! with_bincode_config {
+    ($endianness:expr, $bincode_config:ident => $body:expr) => {
+        match $endianness {
+            $crate::Endianness::Big => {
+                let $bincode_config = bincode::config::standard().with_big_endian();
+                $body
+            }
+            $crate::Endianness::Little => {
+                let $bincode_config = bincode::config::standard().with_little_endian();
+                $body
+            }
+        }
+    };
+}
+
+pub mod any;
+#[cfg(feature = "tokio")]
+mod asynchronous;
+pub mod builder;
+pub mod diff;
 pub mod error;
+#[cfg(feature = "serde_json")]
+mod json;
 pub mod mapper;
+pub mod mirror;
+pub mod table;
 pub mod wrapper;
 
+use builder::DatabaseBuilder;
+use diff::{DiffReport, TableDiff};
 use error::Error;
-use wrapper::Db;
+use table::Table;
+use wrapper::{Db, KeyValuePair, Transaction};
+
+/// Name of the managed column family for the config table.
+///
+/// A descriptor with this name can be passed when creating or opening a database to configure
+/// the column family's options.
+pub const CONFIG_CF_NAME: &str = "_config";
+
+/// Name of the managed column family for the books table.
+///
+/// A descriptor with this name can be passed when creating or opening a database to configure
+/// the column family's options.
+pub const BOOKS_CF_NAME: &str = "_books";
+
+/// Reserved key recording the Rust type name of the value stored in a managed table.
+const TYPE_NAME_KEY: &str = "__type_name";
+
+/// Reserved key recording the endianness used to encode values in the managed tables.
+const ENDIANNESS_KEY: &str = "__endianness";
+
+/// Reserved key recording the caller-defined schema version in the config table.
+const SCHEMA_VERSION_KEY: &str = "__schema_version";
+
+/// Directory name (under the system temporary directory) used for databases in RocksDB's
+/// in-memory environment.
+const IN_MEMORY_DIR_NAME: &str = "rocksdb-store-in-memory";
+
+/// Keys in the managed tables that do not correspond to fields.
+const RESERVED_KEYS: [&str; 5] = [
+    mapper::VARIANT_KEY,
+    mapper::NONE_KEY,
+    TYPE_NAME_KEY,
+    ENDIANNESS_KEY,
+    SCHEMA_VERSION_KEY,
+];
+
+/// Byte order used to encode values in the config and books tables.
+///
+/// This is recorded when a database is created and detected when it is opened. Databases without
+/// a recorded value are big-endian.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Endianness {
+    #[default]
+    Big,
+    Little,
+}
+
+impl Endianness {
+    fn encode<T: serde::ser::Serialize + ?Sized>(
+        self,
+        value: &T,
+    ) -> Result<Vec<u8>, bincode::error::EncodeError> {
+        with_bincode_config!(self, bincode_config => {
+            bincode::serde::encode_to_vec(value, bincode_config)
+        })
+    }
+
+    /// Decode a stored field value, rejecting any bytes left over after decoding.
+    fn decode<T: serde::de::DeserializeOwned>(
+        self,
+        bytes: &[u8],
+        field: &str,
+    ) -> Result<T, mapper::Error> {
+        with_bincode_config!(self, bincode_config => {
+            mapper::decode_field(std::marker::PhantomData, bytes, bincode_config, field)
+        })
+    }
+
+    fn to_bytes(self) -> [u8; 1] {
+        match self {
+            Self::Big => [0],
+            Self::Little => [1],
+        }
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0] => Some(Self::Big),
+            [1] => Some(Self::Little),
+            _ => None,
+        }
+    }
+}
+
+/// Initial delay before retrying a write that failed because of a transaction conflict.
+const CONFLICT_RETRY_BACKOFF: Duration = Duration::from_millis(1);
+
+/// Prefix of the options files that RocksDB writes to the database directory on open.
+const OPTIONS_FILE_PREFIX: &str = "OPTIONS-";
+
+/// Report a writeable open that failed because RocksDB could not take its lock or create files in
+/// the directory with a clear error.
+fn writable_open_error(path: &Path, error: rocksdb::Error) -> Error {
+    let message: &str = error.as_ref();
+
+    if error.kind() == rocksdb::ErrorKind::IOError
+        && (message.contains("Permission denied") || message.contains("Read-only file system"))
+    {
+        Error::NotWritable {
+            path: path.to_path_buf(),
+        }
+    } else {
+        error.into()
+    }
+}
+
+/// Set the TTL in seconds for each named column family, where non-positive values disable expiry.
+fn set_ttls(cfs: &mut [ColumnFamilyDescriptor], ttls: &[(&str, i32)]) {
+    for (cf_name, seconds) in ttls {
+        if let Some(cf) = cfs.iter_mut().find(|cf| cf.name() == *cf_name) {
+            cf.set_ttl(if *seconds > 0 {
+                ColumnFamilyTtl::Duration(Duration::from_secs(*seconds as u64))
+            } else {
+                ColumnFamilyTtl::Disabled
+            });
+        }
+    }
+}
+
+/// An associative merge operator, which combines an existing value with a sequence of operands.
+pub type MergeFunction = fn(&[u8], Option<&[u8]>, &rocksdb::MergeOperands) -> Option<Vec<u8>>;
+
+/// Add a descriptor for each column family with a merge operator.
+fn merge_operator_cfs(
+    mut cfs: Vec<ColumnFamilyDescriptor>,
+    merge_operators: &[(&str, MergeFunction)],
+) -> Vec<ColumnFamilyDescriptor> {
+    for (cf_name, merge_function) in merge_operators {
+        let mut cf_options = Options::default();
+        cf_options.set_merge_operator_associative(*cf_name, *merge_function);
+
+        cfs.push(ColumnFamilyDescriptor::new(*cf_name, cf_options));
+    }
+
+    cfs
+}
+
+/// Check that an existing database has the managed column families, so that opening a database
+/// that was not created by this library fails clearly.
+///
+/// Failures to list the column families are left for RocksDB to report.
+fn check_managed_cfs(options: &Options, path: &Path) -> Result<(), Error> {
+    if let Ok(cf_names) = DB::list_cf(options, path) {
+        for cf_name in [CONFIG_CF_NAME, BOOKS_CF_NAME] {
+            if !cf_names.iter().any(|name| name == cf_name) {
+                return Err(mapper::Error::MissingColumnFamily(cf_name.to_string()).into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Descriptors for both managed column families using the given options, which can be passed
+/// along with other column families when creating or opening a database.
+///
+/// This is mostly useful for compaction settings. The managed tables are small and rewritten as a
+/// whole, so level compaction (the default) keeps reads cheap and space overhead low. Universal
+/// compaction reduces write amplification for write-heavy workloads (for example books that are
+/// updated very frequently), at the cost of temporarily using up to twice the space during
+/// compactions and potentially reading more files.
+pub fn managed_cf_descriptors(options: &Options) -> Vec<ColumnFamilyDescriptor> {
+    [CONFIG_CF_NAME, BOOKS_CF_NAME]
+        .map(|cf_name| ColumnFamilyDescriptor::new(cf_name, options.clone()))
+        .into()
+}
+
+/// Add descriptors for the managed column families, unless they have already been provided.
+fn managed_cfs(mut cfs: Vec<ColumnFamilyDescriptor>) -> (Vec<ColumnFamilyDescriptor>, Vec<String>) {
+    for cf_name in [CONFIG_CF_NAME, BOOKS_CF_NAME] {
+        if !cfs.iter().any(|cf| cf.name() == cf_name) {
+            cfs.push(ColumnFamilyDescriptor::new(cf_name, Options::default()));
+        }
+    }
+
+    let cf_names = cfs.iter().map(|cf| cf.name().to_string()).collect();
+
+    (cfs, cf_names)
+}
+
+/// The kind of transactions used by a writeable database.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum OpenMode {
+    #[default]
+    OptimisticTransactions,
+    PessimisticTransactions,
+    /// Config and books writes are still applied atomically using write batches.
+    WithoutTransactions,
+}
 
-const CONFIG_CF_NAME: &str = "_config";
-const BOOKS_CF_NAME: &str = "_books";
+impl OpenMode {
+    fn from_optimistic(optimistic_transactions: bool) -> Self {
+        if optimistic_transactions {
+            Self::OptimisticTransactions
+        } else {
+            Self::PessimisticTransactions
+        }
+    }
+}
+
+/// A builder for the given path, column families, and options.
+fn builder<P: AsRef<Path>>(
+    path: P,
+    cfs: Vec<ColumnFamilyDescriptor>,
+    options: Options,
+) -> DatabaseBuilder {
+    DatabaseBuilder::new()
+        .path(path.as_ref())
+        .column_families(cfs)
+        .options(options)
+}
+
+impl From<OpenMode> for WriteMode {
+    fn from(value: OpenMode) -> Self {
+        match value {
+            OpenMode::OptimisticTransactions => Self::OptimisticTransactions,
+            OpenMode::PessimisticTransactions => {
+                Self::PessimisticTransactions(TransactionDBOptions::default())
+            }
+            OpenMode::WithoutTransactions => Self::Plain,
+        }
+    }
+}
+
+/// Metadata recorded in the config table when a database is created.
+#[derive(Clone, Copy, Default)]
+struct Metadata {
+    endianness: Endianness,
+    schema_version: Option<u32>,
+}
 
-type ConfigBincodeConfigType = bincode::config::Configuration<bincode::config::BigEndian>;
-type BooksBincodeConfigType = bincode::config::Configuration<bincode::config::BigEndian>;
+/// How a writeable database is opened.
+enum WriteMode {
+    Plain,
+    PlainWithTtl,
+    OptimisticTransactions,
+    PessimisticTransactions(TransactionDBOptions),
+}
 
-const CONFIG_BINCODE_CONFIG: ConfigBincodeConfigType =
-    bincode::config::standard().with_big_endian();
-const BOOKS_BINCODE_CONFIG: BooksBincodeConfigType = bincode::config::standard().with_big_endian();
+impl WriteMode {
+    fn open(
+        self,
+        options: &Options,
+        path: impl AsRef<Path>,
+        cfs: Vec<ColumnFamilyDescriptor>,
+    ) -> Result<Db, rocksdb::Error> {
+        Ok(match self {
+            Self::Plain => Db::read_write(DB::open_cf_descriptors(options, path, cfs)?),
+            // Column families without an explicit TTL never expire, since a zero TTL is infinite.
+            Self::PlainWithTtl => Db::read_write(DB::open_cf_descriptors_with_ttl(
+                options,
+                path,
+                cfs,
+                Duration::ZERO,
+            )?),
+            Self::OptimisticTransactions => {
+                OptimisticTransactionDB::open_cf_descriptors(options, path, cfs)?.into()
+            }
+            Self::PessimisticTransactions(transaction_db_options) => {
+                TransactionDB::open_cf_descriptors(options, &transaction_db_options, path, cfs)?
+                    .into()
+            }
+        })
+    }
+}
 
 #[derive(Clone)]
 pub struct Database<const W: bool, C, B> {
@@ -30,56 +332,478 @@ pub struct Database<const W: bool, C, B> {
     pub books: B,
 }
 
+impl<const W: bool, C, B> std::fmt::Debug for Database<W, C, B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The config and books values are omitted, since they may be large.
+        f.debug_struct("Database")
+            .field("writeable", &W)
+            .field("db", &self.db)
+            .finish_non_exhaustive()
+    }
+}
+
 impl<const W: bool, C, B> Database<W, C, B> {
-    fn config_cf(db: &Db) -> &ColumnFamily {
+    fn config_cf(db: &Db) -> Result<&ColumnFamily, mapper::Error> {
         db.handle(CONFIG_CF_NAME)
-            .expect("Config table column family does not exist")
+            .ok_or_else(|| mapper::Error::MissingColumnFamily(CONFIG_CF_NAME.to_string()))
     }
 
-    fn books_cf(db: &Db) -> &ColumnFamily {
+    fn books_cf(db: &Db) -> Result<&ColumnFamily, mapper::Error> {
         db.handle(BOOKS_CF_NAME)
-            .expect("Books table column family does not exist")
+            .ok_or_else(|| mapper::Error::MissingColumnFamily(BOOKS_CF_NAME.to_string()))
+    }
+
+    fn config_mapper<BC: bincode::config::Config>(
+        db: &Db,
+        bincode_config: BC,
+    ) -> Result<TableMapper<'_, W, BC>, mapper::Error> {
+        Ok(mapper::TableMapper::new(
+            db,
+            Self::config_cf(db)?,
+            bincode_config,
+        ))
+    }
+
+    fn books_mapper<BC: bincode::config::Config>(
+        db: &Db,
+        bincode_config: BC,
+    ) -> Result<TableMapper<'_, W, BC>, mapper::Error> {
+        Ok(mapper::TableMapper::new(
+            db,
+            Self::books_cf(db)?,
+            bincode_config,
+        ))
+    }
+
+    /// The endianness used to encode values in the config and books tables.
+    pub fn endianness(&self) -> Endianness {
+        self.db.endianness()
+    }
+
+    /// Typed access to an arbitrary column family.
+    pub fn table(&self, cf_name: &str) -> Result<Table<'_>, Error> {
+        let cf = self
+            .db
+            .handle(cf_name)
+            .ok_or_else(|| Error::UnknownColumnFamily(cf_name.to_string()))?;
+
+        Ok(Table::new(&self.db, cf))
+    }
+
+    /// Map a struct onto an arbitrary column family, in the same way as the config and books.
+    pub fn table_mapper<BC: bincode::config::Config>(
+        &self,
+        cf_name: &str,
+        bincode_config: BC,
+    ) -> Result<TableMapper<'_, W, BC>, Error> {
+        let cf = self
+            .db
+            .handle(cf_name)
+            .ok_or_else(|| Error::UnknownColumnFamily(cf_name.to_string()))?;
+
+        Ok(mapper::TableMapper::new(&self.db, cf, bincode_config))
+    }
+
+    /// Read a struct from an arbitrary column family, using the config's Bincode configuration.
+    pub fn read_table<T: serde::de::DeserializeOwned>(&self, cf_name: &str) -> Result<T, Error> {
+        with_bincode_config!(self.db.endianness(), bincode_config => {
+            Ok(T::deserialize(&self.table_mapper(cf_name, bincode_config)?)?)
+        })
+    }
+
+    /// Read a single config field without deserializing the whole config.
+    ///
+    /// Returns `None` if the field has not been written.
+    pub fn read_config_field<T: serde::de::DeserializeOwned>(
+        &self,
+        field: &str,
+    ) -> Result<Option<T>, mapper::Error> {
+        Self::read_field(&self.db, Self::config_cf(&self.db)?, field)
+    }
+
+    /// Read a single books field without deserializing the whole books value.
+    ///
+    /// Returns `None` if the field has not been written.
+    pub fn read_books_field<T: serde::de::DeserializeOwned>(
+        &self,
+        field: &str,
+    ) -> Result<Option<T>, mapper::Error> {
+        Self::read_field(&self.db, Self::books_cf(&self.db)?, field)
+    }
+
+    fn read_field<T: serde::de::DeserializeOwned>(
+        db: &Db,
+        cf: &ColumnFamily,
+        field: &str,
+    ) -> Result<Option<T>, mapper::Error> {
+        db.get(cf, field)?
+            .map(|bytes| db.endianness().decode(&bytes, field))
+            .transpose()
+    }
+
+    /// The Rust type name of the config recorded when the database was created.
+    ///
+    /// This is intended for diagnostics, and type names are not guaranteed to be stable across
+    /// compiler versions.
+    pub fn stored_config_type(&self) -> Result<Option<String>, Error> {
+        Ok(Self::read_field(
+            &self.db,
+            Self::config_cf(&self.db)?,
+            TYPE_NAME_KEY,
+        )?)
+    }
+
+    /// The Rust type name of the books recorded when the database was created.
+    pub fn stored_books_type(&self) -> Result<Option<String>, Error> {
+        Ok(Self::read_field(
+            &self.db,
+            Self::books_cf(&self.db)?,
+            TYPE_NAME_KEY,
+        )?)
+    }
+
+    /// The field names that are stored in the config table, which may include fields that have
+    /// since been removed from the config type.
+    ///
+    /// Reserved keys used for metadata are not included.
+    pub fn stored_config_keys(&self) -> Result<Vec<String>, Error> {
+        Self::stored_keys(&self.db, Self::config_cf(&self.db)?)
+    }
+
+    /// The field names and encoded values stored in the config table, in key order.
+    ///
+    /// Reserved keys used for metadata are not included.
+    pub fn raw_config_entries(&self) -> Result<Vec<(String, Vec<u8>)>, Error> {
+        Self::raw_entries(&self.db, Self::config_cf(&self.db)?)
+    }
+
+    /// The field names and encoded values stored in the books table, in key order.
+    ///
+    /// Reserved keys used for metadata are not included.
+    pub fn raw_books_entries(&self) -> Result<Vec<(String, Vec<u8>)>, Error> {
+        Self::raw_entries(&self.db, Self::books_cf(&self.db)?)
+    }
+
+    fn raw_entries(db: &Db, cf: &ColumnFamily) -> Result<Vec<(String, Vec<u8>)>, Error> {
+        field_entries(db, cf)
+            .map(|result| {
+                let (key, value) = result?;
+                let key = String::from_utf8(key.into_vec())
+                    .map_err(|error| Error::InvalidKey(error.into_bytes()))?;
+
+                Ok((key, value.into_vec()))
+            })
+            .collect()
+    }
+
+    /// The total size in bytes of the values stored in the config table.
+    ///
+    /// Keys and reserved metadata values are not included.
+    pub fn config_size_bytes(&self) -> Result<u64, Error> {
+        Self::stored_size_bytes(&self.db, Self::config_cf(&self.db)?)
+    }
+
+    /// The total size in bytes of the values stored in the books table.
+    ///
+    /// Keys and reserved metadata values are not included.
+    pub fn books_size_bytes(&self) -> Result<u64, Error> {
+        Self::stored_size_bytes(&self.db, Self::books_cf(&self.db)?)
+    }
+
+    fn stored_size_bytes(db: &Db, cf: &ColumnFamily) -> Result<u64, Error> {
+        let mut size = 0;
+
+        for result in field_entries(db, cf) {
+            let (_, value) = result?;
+
+            size += value.len() as u64;
+        }
+
+        Ok(size)
+    }
+
+    fn stored_keys(db: &Db, cf: &ColumnFamily) -> Result<Vec<String>, Error> {
+        field_entries(db, cf)
+            .map(|result| {
+                let (key, _) = result?;
+
+                String::from_utf8(key.into_vec())
+                    .map_err(|error| Error::InvalidKey(error.into_bytes()))
+            })
+            .collect()
+    }
+
+    /// The schema version recorded when the database was created.
+    ///
+    /// Databases created without a schema version have version 0.
+    pub fn schema_version(&self) -> Result<u32, Error> {
+        Self::schema_version_with_db(&self.db)
     }
 
-    fn config_mapper(db: &Db) -> TableMapper<'_, W, ConfigBincodeConfigType> {
-        mapper::TableMapper::new(db, Self::config_cf(db), CONFIG_BINCODE_CONFIG)
+    fn schema_version_with_db(db: &Db) -> Result<u32, Error> {
+        Ok(Self::read_field(db, Self::config_cf(db)?, SCHEMA_VERSION_KEY)?.unwrap_or_default())
     }
 
-    fn books_mapper(db: &Db) -> TableMapper<'_, W, BooksBincodeConfigType> {
-        mapper::TableMapper::new(db, Self::books_cf(db), BOOKS_BINCODE_CONFIG)
+    /// Iterate over the books table as a dynamic map from field names to values.
+    pub fn read_books_stream<V: serde::de::DeserializeOwned>(
+        &self,
+    ) -> impl Iterator<Item = Result<(String, V), Error>> + use<'_, W, C, B, V> {
+        let endianness = self.db.endianness();
+
+        let entries: Box<dyn Iterator<Item = Result<KeyValuePair, Error>>> =
+            match Self::books_cf(&self.db) {
+                Ok(cf) => {
+                    Box::new(field_entries(&self.db, cf).map(|result| result.map_err(Error::from)))
+                }
+                Err(error) => Box::new(std::iter::once(Err(error.into()))),
+            };
+
+        entries.map(move |result| {
+            let (key, value) = result?;
+            let key = String::from_utf8(key.into_vec())
+                .map_err(|error| Error::InvalidKey(error.into_bytes()))?;
+            let value = endianness.decode(&value, &key)?;
+
+            Ok((key, value))
+        })
+    }
+
+    /// Compare the managed tables of this database with those of another database, key by key.
+    pub fn compare<const OW: bool, OC, OB>(
+        &self,
+        other: &Database<OW, OC, OB>,
+    ) -> Result<DiffReport, Error> {
+        Ok(DiffReport {
+            config: TableDiff::new(
+                self.db
+                    .iterator(Self::config_cf(&self.db)?, IteratorMode::Start),
+                other
+                    .db
+                    .iterator(Self::config_cf(&other.db)?, IteratorMode::Start),
+            )?,
+            books: TableDiff::new(
+                self.db
+                    .iterator(Self::books_cf(&self.db)?, IteratorMode::Start),
+                other
+                    .db
+                    .iterator(Self::books_cf(&other.db)?, IteratorMode::Start),
+            )?,
+        })
     }
 }
 
 impl<C: serde::ser::Serialize, B: serde::ser::Serialize> Database<true, C, B> {
+    /// Create a database, writing the given config and books.
+    ///
+    /// Database-wide settings in `options` (for example a custom [`rocksdb::Env`] or a rate
+    /// limiter) also apply to the managed tables, which otherwise use default column family
+    /// options unless descriptors for them are included in `cfs`.
     pub fn create<P: AsRef<Path>>(
         path: P,
-        mut cfs: Vec<ColumnFamilyDescriptor>,
-        mut options: Options,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        options: Options,
         optimistic_transactions: bool,
         config: C,
         books: B,
     ) -> Result<Self, Error> {
-        let config_cf = ColumnFamilyDescriptor::new(CONFIG_CF_NAME, Options::default());
-        let books_cf = ColumnFamilyDescriptor::new(BOOKS_CF_NAME, Options::default());
+        builder(path, cfs, options)
+            .mode(OpenMode::from_optimistic(optimistic_transactions))
+            .config(config)
+            .books(books)
+            .create()
+    }
+
+    /// Create a database with pessimistic transactions that are configured with the given options
+    /// (for example lock timeouts).
+    #[deprecated(note = "use `DatabaseBuilder::transaction_db_options` instead")]
+    pub fn create_with_transaction_db_options<P: AsRef<Path>>(
+        path: P,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        options: Options,
+        transaction_db_options: TransactionDBOptions,
+        config: C,
+        books: B,
+    ) -> Result<Self, Error> {
+        builder(path, cfs, options)
+            .transaction_db_options(transaction_db_options)
+            .config(config)
+            .books(books)
+            .create()
+    }
+
+    /// Create a database that is written without transactions.
+    ///
+    /// Config and books writes are still applied atomically using write batches.
+    #[deprecated(note = "use `DatabaseBuilder::without_transactions` instead")]
+    pub fn create_without_transactions<P: AsRef<Path>>(
+        path: P,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        options: Options,
+        config: C,
+        books: B,
+    ) -> Result<Self, Error> {
+        builder(path, cfs, options)
+            .without_transactions()
+            .config(config)
+            .books(books)
+            .create()
+    }
 
-        cfs.push(config_cf);
-        cfs.push(books_cf);
+    /// Create a database with optimistic transactions that is backed by RocksDB's in-memory
+    /// environment, and never touches disk.
+    ///
+    /// The contents are discarded when the last handle to the database is dropped.
+    #[deprecated(note = "use `DatabaseBuilder::in_memory` instead")]
+    pub fn create_in_memory(config: C, books: B) -> Result<Self, Error> {
+        DatabaseBuilder::new()
+            .in_memory()
+            .config(config)
+            .books(books)
+            .create()
+    }
 
+    fn create_internal<P: AsRef<Path>>(
+        path: P,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        mut options: Options,
+        mode: WriteMode,
+        metadata: Metadata,
+        config: C,
+        books: B,
+    ) -> Result<Self, Error> {
         options.create_missing_column_families(true);
         options.create_if_missing(true);
 
-        let db: Db = if optimistic_transactions {
-            OptimisticTransactionDB::open_cf_descriptors(&options, path, cfs)?.into()
-        } else {
-            let transaction_options = TransactionDBOptions::default();
+        let (cfs, cf_names) = managed_cfs(cfs);
+
+        let db = mode
+            .open(&options, path, cfs)?
+            .with_cf_names(cf_names)
+            .with_endianness(metadata.endianness);
+        let database = Self { db, config, books };
+
+        // Safe because the database was just opened for writing.
+        let tx = database.db.transaction().unwrap();
+        database.write_config_in(&tx, &database.config)?;
+        database.write_books_in(&tx, &database.books)?;
+        Self::write_metadata_in(&database.db, &tx, metadata.schema_version)?;
+        tx.commit()?;
+
+        Ok(database)
+    }
+
+    /// Flush the column families of this database, including the managed tables.
+    ///
+    /// This fails with [`Error::Unsupported`] for databases using pessimistic transactions.
+    pub fn flush(&self) -> Result<(), Error> {
+        self.db.flush()
+    }
+
+    /// Compact the given key range of a column family, where missing bounds are unbounded.
+    ///
+    /// This fails with [`Error::Unsupported`] for databases using pessimistic transactions.
+    pub fn compact_range<S: AsRef<[u8]>, E: AsRef<[u8]>>(
+        &self,
+        cf_name: &str,
+        start: Option<S>,
+        end: Option<E>,
+    ) -> Result<(), Error> {
+        let cf = self
+            .db
+            .handle(cf_name)
+            .ok_or_else(|| Error::UnknownColumnFamily(cf_name.to_string()))?;
+
+        self.db.compact_range(cf, start, end)
+    }
+
+    /// Flush the column families of this database when the last handle to it is dropped.
+    ///
+    /// This is not supported for databases using pessimistic transactions.
+    pub fn set_flush_on_drop(&self, flush_on_drop: bool) {
+        self.db.set_flush_on_drop(flush_on_drop);
+    }
+
+    /// Serialize config and books writes made through this handle and its clones.
+    ///
+    /// This is useful with optimistic transactions, where concurrent writers would otherwise
+    /// frequently fail with conflicts.
+    pub fn set_serialize_writes(&self, serialize_writes: bool) {
+        self.db.set_serialize_writes(serialize_writes);
+    }
+
+    /// Create a checkpoint and verify it by reading every entry with checksum verification.
+    ///
+    /// If verification fails, the checkpoint directory is deleted. This is not supported for
+    /// databases using pessimistic transactions.
+    pub fn checkpoint_and_verify<P: AsRef<Path>>(&self, target: P) -> Result<(), Error> {
+        let target = target.as_ref();
+
+        self.db.checkpoint(target)?;
+
+        if let Err(error) = Self::verify_checkpoint(target) {
+            // The verification error is more useful than any failure to clean up.
+            let _ = std::fs::remove_dir_all(target);
+
+            return Err(error);
+        }
+
+        Ok(())
+    }
 
-            TransactionDB::open_cf_descriptors(&options, &transaction_options, path, cfs)?.into()
+    fn verify_checkpoint(path: &Path) -> Result<(), Error> {
+        let mut options = Options::default();
+        options.set_paranoid_checks(true);
+
+        let cf_names = DB::list_cf(&options, path)?;
+        let admin = Admin {
+            underlying: DB::open_cf_for_read_only(&options, path, &cf_names, false)?,
+            cf_names,
+            options,
         };
 
-        Self::write_config_with_db(&db, &config)?;
-        Self::write_books_with_db(&db, &books)?;
+        Ok(admin.verify_checksums()?)
+    }
 
-        Ok(Self { db, config, books })
+    /// Begin a transaction that can be used to write both tables atomically.
+    pub fn transaction(&self) -> Option<Transaction<'_>> {
+        self.db.transaction()
+    }
+
+    /// Write the config into an existing transaction, which the caller must commit.
+    ///
+    /// Writes made this way are not serialized by [`Database::set_serialize_writes`].
+    pub fn write_config_in(&self, tx: &Transaction<'_>, config: &C) -> Result<(), mapper::Error> {
+        with_bincode_config!(self.db.endianness(), bincode_config => {
+            config.serialize(mapper::TableMapper::with_transaction(
+                &self.db,
+                tx,
+                Self::config_cf(&self.db)?,
+                bincode_config,
+            ))
+        })
+    }
+
+    /// Write the books into an existing transaction, which the caller must commit.
+    ///
+    /// Writes made this way are not serialized by [`Database::set_serialize_writes`].
+    pub fn write_books_in(&self, tx: &Transaction<'_>, books: &B) -> Result<(), mapper::Error> {
+        with_bincode_config!(self.db.endianness(), bincode_config => {
+            books.serialize(mapper::TableMapper::with_transaction(
+                &self.db,
+                tx,
+                Self::books_cf(&self.db)?,
+                bincode_config,
+            ))
+        })
+    }
+
+    /// Install or remove a callback that is invoked with the table name and total value size of
+    /// each committed write.
+    ///
+    /// Writes made with [`Database::write_config_in`] or [`Database::write_books_in`] are not
+    /// reported.
+    pub fn set_on_write(&self, on_write: Option<wrapper::OnWrite>) {
+        self.db.set_on_write(on_write);
     }
 
     pub fn write_config(&self, config: &C) -> Result<(), mapper::Error> {
@@ -90,179 +814,3202 @@ impl<C: serde::ser::Serialize, B: serde::ser::Serialize> Database<true, C, B> {
         Self::write_books_with_db(&self.db, books)
     }
 
+    /// Write the config, retrying up to `max_retries` times (with a linear backoff) if the commit
+    /// fails because of a transaction conflict.
+    ///
+    /// Other errors are returned immediately.
+    pub fn write_config_retry(&self, config: &C, max_retries: usize) -> Result<(), mapper::Error> {
+        let mut retries = 0;
+
+        loop {
+            match self.write_config(config) {
+                Err(mapper::Error::Db(error))
+                    if retries < max_retries
+                        && matches!(
+                            error.kind(),
+                            rocksdb::ErrorKind::Busy | rocksdb::ErrorKind::TryAgain
+                        ) =>
+                {
+                    retries += 1;
+                    std::thread::sleep(CONFLICT_RETRY_BACKOFF * retries as u32);
+                }
+                result => return result,
+            }
+        }
+    }
+
     fn write_config_with_db(db: &Db, config: &C) -> Result<(), mapper::Error> {
-        config.serialize(Self::config_mapper(db))
+        let _guard = db.write_guard();
+
+        with_bincode_config!(db.endianness(), bincode_config => {
+            config.serialize(Self::config_mapper(db, bincode_config)?)
+        })
     }
 
     fn write_books_with_db(db: &Db, books: &B) -> Result<(), mapper::Error> {
-        books.serialize(Self::books_mapper(db))
+        let _guard = db.write_guard();
+
+        with_bincode_config!(db.endianness(), bincode_config => {
+            books.serialize(Self::books_mapper(db, bincode_config)?)
+        })
+    }
+
+    fn write_metadata_in(
+        db: &Db,
+        tx: &Transaction<'_>,
+        schema_version: Option<u32>,
+    ) -> Result<(), Error> {
+        let endianness = db.endianness();
+        let config_type_name = endianness
+            .encode(std::any::type_name::<C>())
+            .map_err(mapper::Error::from)?;
+        let books_type_name = endianness
+            .encode(std::any::type_name::<B>())
+            .map_err(mapper::Error::from)?;
+
+        tx.put(Self::config_cf(db)?, ENDIANNESS_KEY, endianness.to_bytes())?;
+        tx.put(Self::config_cf(db)?, TYPE_NAME_KEY, config_type_name)?;
+        tx.put(Self::books_cf(db)?, TYPE_NAME_KEY, books_type_name)?;
+
+        if let Some(schema_version) = schema_version {
+            let schema_version = endianness
+                .encode(&schema_version)
+                .map_err(mapper::Error::from)?;
+
+            tx.put(Self::config_cf(db)?, SCHEMA_VERSION_KEY, schema_version)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<C, B> Database<true, C, B>
+where
+    C: serde::ser::Serialize + serde::de::DeserializeOwned,
+    B: serde::ser::Serialize,
+{
+    /// Read the stored config, apply `f` to it, and write it back in a single transaction.
+    ///
+    /// A concurrent update to the stored config causes the commit to fail with a conflict error.
+    /// This fails with [`Error::Unsupported`] for databases without transactions.
+    pub fn update_config<F: FnOnce(&mut C)>(&self, f: F) -> Result<(), Error> {
+        let _guard = self.db.write_guard();
+        let (tx, mut config) = self.read_config_for_update()?;
+
+        f(&mut config);
+
+        self.write_config_in(&tx, &config)?;
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Begin a transaction and read the stored config in it, so that a concurrent update to any
+    /// of its fields (including fields that are not currently stored) causes the commit to fail.
+    fn read_config_for_update(&self) -> Result<(Transaction<'_>, C), Error> {
+        if self.db.as_optimistic().is_none() && self.db.as_pessimistic().is_none() {
+            return Err(Error::Unsupported);
+        }
+
+        // Safe because we know the wrapper is writeable.
+        let tx = self.db.transaction().unwrap();
+
+        let config = with_bincode_config!(self.db.endianness(), bincode_config => {
+            C::deserialize(&mapper::TableMapper::with_transaction(
+                &self.db,
+                &tx,
+                Self::config_cf(&self.db)?,
+                bincode_config,
+            ))
+        })?;
+
+        Ok((tx, config))
+    }
+}
+
+impl<C, B> Database<true, C, B>
+where
+    C: serde::ser::Serialize + serde::de::DeserializeOwned + PartialEq,
+    B: serde::ser::Serialize,
+{
+    /// Write a new config in a single transaction if the stored config is equal to `expected`,
+    /// returning whether the config was written.
+    ///
+    /// A concurrent update to the stored config causes the commit to fail with a conflict error.
+    /// This fails with [`Error::Unsupported`] for databases without transactions.
+    pub fn compare_and_swap_config(&self, expected: &C, new: &C) -> Result<bool, Error> {
+        let _guard = self.db.write_guard();
+        let (tx, current) = self.read_config_for_update()?;
+
+        if current != *expected {
+            return Ok(false);
+        }
+
+        self.write_config_in(&tx, new)?;
+        tx.commit()?;
+
+        Ok(true)
     }
 }
 
 impl<'de, C: serde::de::Deserialize<'de>, B: serde::de::Deserialize<'de>> Database<true, C, B> {
+    /// Open a database for writing with the given kind of transactions.
+    #[deprecated(note = "use `DatabaseBuilder::mode` instead")]
+    pub fn open_with_mode<P: AsRef<Path>>(
+        path: P,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        options: Options,
+        mode: OpenMode,
+    ) -> Result<Self, Error> {
+        Database::open_internal(path, cfs, options, mode.into())
+    }
+
+    #[deprecated(note = "use `DatabaseBuilder::pessimistic` instead")]
     pub fn open_with_pessimistic_transactions<P: AsRef<Path>>(
         path: P,
         cfs: Vec<ColumnFamilyDescriptor>,
         options: Options,
     ) -> Result<Self, Error> {
-        Database::open_internal(path, cfs, options, true)
+        Database::open_internal(path, cfs, options, OpenMode::PessimisticTransactions.into())
     }
-}
 
-impl<'de, const W: bool, C: serde::de::Deserialize<'de>, B: serde::de::Deserialize<'de>>
-    Database<W, C, B>
-{
-    pub fn open<P: AsRef<Path>>(
+    /// Open a database with pessimistic transactions that are configured with the given options
+    /// (for example lock timeouts).
+    #[deprecated(note = "use `DatabaseBuilder::transaction_db_options` instead")]
+    pub fn open_with_transaction_db_options<P: AsRef<Path>>(
         path: P,
         cfs: Vec<ColumnFamilyDescriptor>,
         options: Options,
+        transaction_db_options: TransactionDBOptions,
     ) -> Result<Self, Error> {
-        Self::open_internal(path, cfs, options, true)
+        Database::open_internal(
+            path,
+            cfs,
+            options,
+            WriteMode::PessimisticTransactions(transaction_db_options),
+        )
     }
 
-    pub fn read_config(&self) -> Result<C, mapper::Error> {
-        Self::read_config_with_db(&self.db)
-    }
+    /// Open a database for writing without transactions.
+    ///
+    /// Config and books writes are still applied atomically using write batches.
+    #[deprecated(note = "use `DatabaseBuilder::without_transactions` instead")]
+    pub fn open_without_transactions<P: AsRef<Path>>(
+        path: P,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        options: Options,
+    ) -> Result<Self, Error> {
+        Database::open_internal(path, cfs, options, OpenMode::WithoutTransactions.into())
+    }
+}
+
+impl<'de, C, B> Database<true, C, B>
+where
+    C: serde::ser::Serialize + serde::de::Deserialize<'de>,
+    B: serde::ser::Serialize + serde::de::Deserialize<'de>,
+{
+    /// Open the database at the given path if one exists (reading the stored config and books),
+    /// and otherwise create it with the given default values.
+    ///
+    /// A database is considered to exist if the path contains a RocksDB `CURRENT` file. Errors
+    /// opening an existing database are returned, and never cause it to be recreated.
+    pub fn open_or_create<P: AsRef<Path>>(
+        path: P,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        options: Options,
+        mode: OpenMode,
+        default_config: C,
+        default_books: B,
+    ) -> Result<Self, Error> {
+        if path.as_ref().join("CURRENT").exists() {
+            Self::open_internal(path, cfs, options, mode.into())
+        } else {
+            Self::create_internal(
+                path,
+                cfs,
+                options,
+                mode.into(),
+                Metadata::default(),
+                default_config,
+                default_books,
+            )
+        }
+    }
+}
+
+impl<'de, const W: bool, C: serde::de::Deserialize<'de>, B: serde::de::Deserialize<'de>>
+    Database<W, C, B>
+{
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        options: Options,
+    ) -> Result<Self, Error> {
+        Self::open_internal(path, cfs, options, WriteMode::OptimisticTransactions)
+    }
+
+    pub fn read_config(&self) -> Result<C, mapper::Error> {
+        Self::read_config_with_db(&self.db)
+    }
+
+    pub fn read_books(&self) -> Result<B, mapper::Error> {
+        Self::read_books_with_db(&self.db)
+    }
+
+    fn open_internal<P: AsRef<Path>>(
+        path: P,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        options: Options,
+        mode: WriteMode,
+    ) -> Result<Self, Error> {
+        Self::from_db(Self::open_db(path, cfs, options, mode)?)
+    }
+
+    /// Open a database, checking the recorded schema version (if one is expected) before decoding
+    /// the config and books, since they may not decode with a mismatched schema.
+    fn open_checked<P: AsRef<Path>>(
+        path: P,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        options: Options,
+        mode: WriteMode,
+        expected_schema_version: Option<u32>,
+    ) -> Result<Self, Error> {
+        let db = Self::with_stored_endianness(Self::open_db(path, cfs, options, mode)?)?;
+
+        if let Some(expected) = expected_schema_version {
+            let found = Self::schema_version_with_db(&db)?;
+
+            if found != expected {
+                return Err(Error::SchemaMismatch { expected, found });
+            }
+        }
+
+        Self::read_tables(db)
+    }
+
+    fn open_db<P: AsRef<Path>>(
+        path: P,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        options: Options,
+        mode: WriteMode,
+    ) -> Result<Db, Error> {
+        check_managed_cfs(&options, path.as_ref())?;
+
+        let (cfs, cf_names) = managed_cfs(cfs);
+
+        let db: Db = if W {
+            mode.open(&options, path.as_ref(), cfs)
+                .map_err(|error| writable_open_error(path.as_ref(), error))?
+        } else {
+            DB::open_cf_descriptors_read_only(&options, path, cfs, false)?.into()
+        };
+
+        Ok(db.with_cf_names(cf_names))
+    }
+
+    fn from_db(db: Db) -> Result<Self, Error> {
+        Self::read_tables(Self::with_stored_endianness(db)?)
+    }
+
+    fn with_stored_endianness(db: Db) -> Result<Db, Error> {
+        let endianness = match db.get(Self::config_cf(&db)?, ENDIANNESS_KEY)? {
+            Some(bytes) => {
+                Endianness::from_bytes(&bytes).ok_or_else(|| Error::InvalidValue(bytes.to_vec()))?
+            }
+            None => Endianness::default(),
+        };
+
+        Ok(db.with_endianness(endianness))
+    }
+
+    fn read_tables(db: Db) -> Result<Self, Error> {
+        let config = Self::read_config_with_db(&db)?;
+        let books = Self::read_books_with_db(&db)?;
+
+        Ok(Self { db, config, books })
+    }
+
+    fn read_config_with_db(db: &Db) -> Result<C, mapper::Error> {
+        with_bincode_config!(db.endianness(), bincode_config => {
+            C::deserialize(&Self::config_mapper(db, bincode_config)?)
+        })
+    }
+
+    fn read_books_with_db(db: &Db) -> Result<B, mapper::Error> {
+        with_bincode_config!(db.endianness(), bincode_config => {
+            B::deserialize(&Self::books_mapper(db, bincode_config)?)
+        })
+    }
+}
+
+impl<const W: bool, C, B> Database<W, C, B>
+where
+    C: serde::ser::Serialize + serde::de::DeserializeOwned + Default,
+{
+    /// Read the config, using the default value for any field that cannot be decoded.
+    pub fn read_config_lenient(&self) -> Result<C, Error> {
+        self.read_config_lenient_with(|_, _| {})
+    }
+
+    /// Read the config, using the default value for any field that cannot be decoded, and
+    /// reporting each such field and its decoding error to the callback.
+    pub fn read_config_lenient_with<F: FnMut(&str, &mapper::Error)>(
+        &self,
+        mut on_fallback: F,
+    ) -> Result<C, Error> {
+        with_bincode_config!(self.db.endianness(), bincode_config => {
+            let cf = Self::config_cf(&self.db)?;
+            let mut defaults = HashMap::new();
+
+            C::default().serialize(mapper::TableMapper::capturing(
+                &self.db,
+                cf,
+                bincode_config,
+                &mut defaults,
+            ))?;
+
+            let mapper = Self::config_mapper(&self.db, bincode_config)?.with_defaults(defaults);
+            let config = C::deserialize(&mapper)?;
+
+            for (field, error) in mapper.take_fallbacks() {
+                on_fallback(&field, &error);
+            }
+
+            Ok(config)
+        })
+    }
+}
+
+impl<const W: bool, C, B> Database<W, C, B>
+where
+    C: serde::de::DeserializeOwned + Send + 'static,
+    B: serde::de::DeserializeOwned + Send + 'static,
+{
+    /// Open a database on a background thread, failing with [`Error::OpenTimedOut`] if this takes
+    /// longer than the given timeout.
+    ///
+    /// RocksDB cannot cancel an open, so on timeout the background thread is abandoned and keeps
+    /// running. It continues to hold its file lock and memory until the open completes, after which
+    /// the database is closed, so retrying immediately may fail.
+    pub fn open_with_timeout<P: AsRef<Path>>(
+        path: P,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        options: Options,
+        timeout: Duration,
+    ) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            // The receiver will have been dropped if we have timed out.
+            let _ = sender.send(Self::open(path, cfs, options));
+        });
+
+        receiver
+            .recv_timeout(timeout)
+            .map_err(|_| Error::OpenTimedOut)?
+    }
+}
+
+impl<C, B> Database<true, C, B> {
+    /// Write a struct to an arbitrary column family in a single transaction, using the config's
+    /// Bincode configuration.
+    pub fn write_table<T: serde::ser::Serialize>(
+        &self,
+        cf_name: &str,
+        value: &T,
+    ) -> Result<(), Error> {
+        let _guard = self.db.write_guard();
+
+        with_bincode_config!(self.db.endianness(), bincode_config => {
+            Ok(value.serialize(self.table_mapper(cf_name, bincode_config)?)?)
+        })
+    }
+
+    /// Update a single config field in its own transaction.
+    ///
+    /// This does not update the cached `config` value, which callers should refresh with
+    /// [`Database::read_config`] if needed.
+    pub fn write_config_field<T: serde::ser::Serialize>(
+        &self,
+        field: &'static str,
+        value: &T,
+    ) -> Result<(), mapper::Error> {
+        Self::write_field(&self.db, Self::config_cf(&self.db)?, field, value)
+    }
+
+    /// Update a single books field in its own transaction.
+    ///
+    /// This does not update the cached `books` value, which callers should refresh with
+    /// [`Database::read_books`] if needed.
+    pub fn write_books_field<T: serde::ser::Serialize>(
+        &self,
+        field: &'static str,
+        value: &T,
+    ) -> Result<(), mapper::Error> {
+        Self::write_field(&self.db, Self::books_cf(&self.db)?, field, value)
+    }
+
+    fn write_field<T: serde::ser::Serialize>(
+        db: &Db,
+        cf: &ColumnFamily,
+        field: &str,
+        value: &T,
+    ) -> Result<(), mapper::Error> {
+        let _guard = db.write_guard();
+        let value_bytes = db.endianness().encode(value)?;
+        let bytes = value_bytes.len();
+        // Safe because we know the wrapper is writeable.
+        let tx = db.transaction().unwrap();
+
+        tx.put(cf, field.as_bytes(), value_bytes)?;
+        tx.commit()?;
+        db.record_write(cf, bytes);
+
+        Ok(())
+    }
+
+    /// Insert or replace entries in the books table in a single transaction.
+    ///
+    /// This is intended for books that are treated as a dynamic map, and leaves other entries
+    /// untouched.
+    pub fn insert_books_entries<V: serde::ser::Serialize, I: IntoIterator<Item = (String, V)>>(
+        &self,
+        entries: I,
+    ) -> Result<(), Error> {
+        self.insert_books_entries_with_options(entries, WriteOptions::default())
+    }
+
+    /// Insert or replace entries in the books table in a single transaction using the given write
+    /// options.
+    ///
+    /// If `no_slowdown` is set and the write would stall, this fails with [`Error::WouldBlock`].
+    pub fn insert_books_entries_with_options<
+        V: serde::ser::Serialize,
+        I: IntoIterator<Item = (String, V)>,
+    >(
+        &self,
+        entries: I,
+        write_options: WriteOptions,
+    ) -> Result<(), Error> {
+        let _guard = self.db.write_guard();
+        let cf = Self::books_cf(&self.db)?;
+        // Safe because we know the wrapper is writeable.
+        let tx = self.db.transaction_with_options(write_options).unwrap();
+        let mut written_bytes = 0;
+
+        for (key, value) in entries {
+            let value_bytes = self
+                .db
+                .endianness()
+                .encode(&value)
+                .map_err(mapper::Error::from)?;
+            written_bytes += value_bytes.len();
+
+            tx.put(cf, key.as_bytes(), value_bytes)?;
+        }
+
+        tx.commit().map_err(|error| {
+            if error.kind() == rocksdb::ErrorKind::Incomplete {
+                Error::WouldBlock
+            } else {
+                Error::from(error)
+            }
+        })?;
+        self.db.record_write(cf, written_bytes);
+
+        Ok(())
+    }
+
+    /// Remove entries from the books table in a single transaction.
+    pub fn remove_books_entries<I: IntoIterator<Item = String>>(
+        &self,
+        keys: I,
+    ) -> Result<(), Error> {
+        let _guard = self.db.write_guard();
+        let cf = Self::books_cf(&self.db)?;
+        // Safe because we know the wrapper is writeable.
+        let tx = self.db.transaction().unwrap();
+
+        for key in keys {
+            tx.delete(cf, key.as_bytes())?;
+        }
+
+        Ok(tx.commit()?)
+    }
+
+    /// Remove any fields from the config table that are not in `known_fields` in a single
+    /// transaction, returning the number of fields removed.
+    ///
+    /// Reserved keys used for metadata are never removed.
+    pub fn prune_config(&self, known_fields: &[&str]) -> Result<usize, Error> {
+        let _guard = self.db.write_guard();
+        let cf = Self::config_cf(&self.db)?;
+        // Safe because we know the wrapper is writeable.
+        let tx = self.db.transaction().unwrap();
+        let mut count = 0;
+
+        for key in Self::stored_keys(&self.db, cf)? {
+            if !known_fields.contains(&key.as_str()) {
+                tx.delete(cf, key.as_bytes())?;
+                count += 1;
+            }
+        }
+
+        tx.commit()?;
+
+        Ok(count)
+    }
+
+    pub fn admin<P: AsRef<Path>>(
+        path: P,
+        cfs: Vec<ColumnFamilyDescriptor>,
+    ) -> Result<Admin, Error> {
+        Self::admin_with_options(path, cfs, Options::default())
+    }
+
+    /// Open an admin handle with the given options.
+    ///
+    /// The options are retained, so that statistics enabled here can be read from the handle.
+    pub fn admin_with_options<P: AsRef<Path>>(
+        path: P,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        options: Options,
+    ) -> Result<Admin, Error> {
+        check_managed_cfs(&options, path.as_ref())?;
+
+        let (cfs, cf_names) = managed_cfs(cfs);
+
+        Ok(Admin {
+            underlying: DB::open_cf_descriptors(&options, path, cfs)?,
+            cf_names,
+            options,
+        })
+    }
+}
+
+impl<'de, C: serde::de::Deserialize<'de>, B: serde::de::Deserialize<'de>> Database<false, C, B> {
+    /// Open a database read-only, optionally failing if a write-ahead log file exists.
+    ///
+    /// The other read-only open methods tolerate write-ahead log files.
+    pub fn open_read_only<P: AsRef<Path>>(
+        path: P,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        options: Options,
+        error_if_log_file_exist: bool,
+    ) -> Result<Self, Error> {
+        check_managed_cfs(&options, path.as_ref())?;
+
+        let (cfs, cf_names) = managed_cfs(cfs);
+        let db: Db =
+            DB::open_cf_descriptors_read_only(&options, path, cfs, error_if_log_file_exist)?.into();
+
+        Self::from_db(db.with_cf_names(cf_names))
+    }
+
+    /// Open a database as a RocksDB secondary instance that can follow a live primary.
+    ///
+    /// The secondary keeps its own logs in `secondary_path`, which must differ from `path`.
+    pub fn open_as_secondary<P: AsRef<Path>, S: AsRef<Path>>(
+        path: P,
+        secondary_path: S,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        mut options: Options,
+    ) -> Result<Self, Error> {
+        // Secondary instances require all files to be kept open.
+        options.set_max_open_files(-1);
+
+        check_managed_cfs(&options, path.as_ref())?;
+
+        let (cfs, cf_names) = managed_cfs(cfs);
+        let db: Db = DB::open_cf_descriptors_as_secondary(
+            &options,
+            path.as_ref(),
+            secondary_path.as_ref(),
+            cfs,
+        )?
+        .into();
+
+        Self::from_db(db.with_cf_names(cf_names))
+    }
+
+    /// Apply any new writes from the primary and re-read the config and books.
+    ///
+    /// Only supported for databases opened with [`Database::open_as_secondary`].
+    pub fn try_catch_up_with_primary(&mut self) -> Result<(), Error> {
+        self.underlying().try_catch_up_with_primary()?;
+
+        self.config = self.read_config()?;
+        self.books = self.read_books()?;
+
+        Ok(())
+    }
+}
+
+impl<C, B> Database<false, C, B> {
+    pub fn underlying(&self) -> &DB {
+        // Safe because we know statically that the database is read-only.
+        self.db.read_only().unwrap()
+    }
+}
+
+pub struct Admin {
+    underlying: DB,
+    cf_names: Vec<String>,
+    options: Options,
+}
+
+impl Admin {
+    /// A dump of RocksDB's internal statistics.
+    ///
+    /// Returns `None` if statistics were not enabled in the options used to open the handle.
+    pub fn statistics_string(&self) -> Option<String> {
+        self.options.get_statistics()
+    }
+
+    /// The current value of a RocksDB statistics counter.
+    ///
+    /// This is always zero if statistics were not enabled in the options used to open the handle.
+    pub fn statistics_ticker(&self, ticker: rocksdb::statistics::Ticker) -> u64 {
+        self.options.get_ticker_count(ticker)
+    }
+
+    pub fn flush(&self) -> Result<(), rocksdb::Error> {
+        for cf_name in &self.cf_names {
+            if let Some(cf) = self.underlying.cf_handle(cf_name) {
+                self.underlying.flush_cf(cf)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn compact(&self) -> Result<(), rocksdb::Error> {
+        let mut options = rocksdb::CompactOptions::default();
+        options.set_change_level(true);
+
+        for cf_name in &self.cf_names {
+            if let Some(cf) = self.underlying.cf_handle(cf_name) {
+                self.underlying
+                    .compact_range_cf_opt::<&[u8], &[u8]>(cf, None, None, &options);
+            }
+        }
+
+        self.underlying.wait_for_compact(&Default::default())
+    }
+
+    /// Read every entry of every known column family with block checksum verification enabled,
+    /// returning the first corruption (or other read error) encountered.
+    pub fn verify_checksums(&self) -> Result<(), rocksdb::Error> {
+        for cf_name in &self.cf_names {
+            if let Some(cf) = self.underlying.cf_handle(cf_name) {
+                let mut read_options = rocksdb::ReadOptions::default();
+                read_options.set_verify_checksums(true);
+                read_options.fill_cache(false);
+
+                let mut iterator = self.underlying.raw_iterator_cf_opt(cf, read_options);
+                iterator.seek_to_first();
+
+                while iterator.valid() {
+                    iterator.next();
+                }
+
+                iterator.status()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Metadata for the live SST files of all column families, including each file's size, level,
+    /// and smallest and largest keys.
+    ///
+    /// Files are grouped by column family name, and then ordered by level and file name.
+    pub fn live_files(&self) -> Result<Vec<rocksdb::LiveFile>, rocksdb::Error> {
+        let mut live_files = self.underlying.live_files()?;
+
+        live_files.sort_by(|a, b| {
+            (&a.column_family_name, a.level, &a.name).cmp(&(
+                &b.column_family_name,
+                b.level,
+                &b.name,
+            ))
+        });
+
+        Ok(live_files)
+    }
+
+    /// Look up a RocksDB property for a known column family.
+    ///
+    /// Returns `None` if the column family or property does not exist.
+    pub fn property_value(
+        &self,
+        cf_name: &str,
+        property: &str,
+    ) -> Result<Option<String>, rocksdb::Error> {
+        match self.cf_handle(cf_name) {
+            Some(cf) => self.underlying.property_value_cf(cf, property),
+            None => Ok(None),
+        }
+    }
+
+    /// Look up an integer-valued RocksDB property for a known column family.
+    ///
+    /// Returns `None` if the column family or property does not exist.
+    pub fn property_int(
+        &self,
+        cf_name: &str,
+        property: &str,
+    ) -> Result<Option<u64>, rocksdb::Error> {
+        match self.cf_handle(cf_name) {
+            Some(cf) => self.underlying.property_int_value_cf(cf, property),
+            None => Ok(None),
+        }
+    }
+
+    /// Summarize the effective compression and block size of a known column family, as recorded
+    /// by RocksDB in the latest options file in the database directory.
+    ///
+    /// Returns `None` if the column family is unknown or the options file cannot be read.
+    pub fn cf_options_summary(&self, cf_name: &str) -> Option<String> {
+        self.cf_handle(cf_name)?;
+
+        let options_path = std::fs::read_dir(self.underlying.path())
+            .ok()?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let number = path
+                    .file_name()?
+                    .to_str()?
+                    .strip_prefix(OPTIONS_FILE_PREFIX)?
+                    .parse::<u64>()
+                    .ok()?;
+
+                Some((number, path))
+            })
+            .max_by_key(|(number, _)| *number)?
+            .1;
+
+        let contents = std::fs::read_to_string(options_path).ok()?;
+        let cf_section = format!("[CFOptions \"{cf_name}\"]");
+        let table_section = format!("[TableOptions/BlockBasedTable \"{cf_name}\"]");
+        let mut section = "";
+        let mut compression = None;
+        let mut block_size = None;
+
+        for line in contents.lines().map(str::trim) {
+            if line.starts_with('[') {
+                section = line;
+            } else if section == cf_section {
+                compression = compression.or_else(|| line.strip_prefix("compression="));
+            } else if section == table_section {
+                block_size = block_size.or_else(|| line.strip_prefix("block_size="));
+            }
+        }
+
+        Some(format!(
+            "compression={}; block_size={}",
+            compression?, block_size?
+        ))
+    }
+
+    fn cf_handle(&self, cf_name: &str) -> Option<&ColumnFamily> {
+        if self.cf_names.iter().any(|known| known == cf_name) {
+            self.underlying.cf_handle(cf_name)
+        } else {
+            None
+        }
+    }
+
+    /// Create a hard-linked checkpoint of the database in a directory that does not yet exist.
+    pub fn checkpoint<P: AsRef<Path>>(&self, target: P) -> Result<(), Error> {
+        let target = target.as_ref();
+
+        if target.exists() {
+            return Err(Error::CheckpointTargetExists {
+                path: target.to_path_buf(),
+            });
+        }
+
+        Ok(rocksdb::checkpoint::Checkpoint::new(&self.underlying)?.create_checkpoint(target)?)
+    }
+
+    /// The total size of the SST files in all known column families.
+    pub fn total_size(&self) -> Result<u64, rocksdb::Error> {
+        let mut total = 0;
+
+        for cf_name in &self.cf_names {
+            if let Some(cf) = self.underlying.cf_handle(cf_name) {
+                total += self
+                    .underlying
+                    .property_int_value_cf(cf, rocksdb::properties::TOTAL_SST_FILES_SIZE)?
+                    .unwrap_or_default();
+            }
+        }
+
+        Ok(total)
+    }
+
+    /// Delete every key in a known column family.
+    ///
+    /// Unknown column families are ignored.
+    pub fn clear_cf(&self, cf_name: &str) -> Result<(), rocksdb::Error> {
+        if let Some(cf) = self.cf_handle(cf_name) {
+            let mut batch = rocksdb::WriteBatch::default();
+
+            for result in self.underlying.iterator_cf(cf, IteratorMode::Start) {
+                let (key, _) = result?;
+                batch.delete_cf(cf, key);
+            }
+
+            self.underlying.write(batch)?;
+        }
+
+        Ok(())
+    }
+
+    /// Delete SST files whose keys fall entirely within the given range, without compaction.
+    ///
+    /// Files that straddle either end of the range are kept, so some data in the range may remain.
+    /// Files in level 0 are never deleted. This fails with [`Error::UnknownColumnFamily`] for
+    /// column families that the handle was not opened with.
+    pub fn delete_files_in_range<K: AsRef<[u8]>>(
+        &self,
+        cf_name: &str,
+        from: K,
+        to: K,
+    ) -> Result<(), Error> {
+        let cf = self
+            .cf_handle(cf_name)
+            .ok_or_else(|| Error::UnknownColumnFamily(cf_name.to_string()))?;
+
+        Ok(self.underlying.delete_file_in_range_cf(cf, from, to)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_arbitrary_derive::QuickCheck;
+
+    #[derive(
+        Clone,
+        Copy,
+        Debug,
+        Default,
+        Eq,
+        PartialEq,
+        QuickCheck,
+        serde_derive::Deserialize,
+        serde_derive::Serialize,
+    )]
+    pub enum Hashes {
+        #[default]
+        Both,
+        Md5Only,
+        Sha256Only,
+    }
+
+    #[derive(
+        Clone,
+        Debug,
+        Default,
+        Eq,
+        PartialEq,
+        QuickCheck,
+        serde_derive::Deserialize,
+        serde_derive::Serialize,
+    )]
+    struct Config {
+        hashes: Hashes,
+        case_sensitive: bool,
+    }
+
+    #[derive(
+        Clone, Debug, Eq, PartialEq, QuickCheck, serde_derive::Deserialize, serde_derive::Serialize,
+    )]
+    struct Books {
+        last_scrape_ms: u64,
+        region: String,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, serde_derive::Deserialize, serde_derive::Serialize)]
+    enum Source {
+        Archive { url: String, hashes: Hashes },
+        Mirror { host: String, port: u16 },
+        Local,
+    }
+
+    impl quickcheck::Arbitrary for Source {
+        fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+            match g.choose(&[0, 1, 2]).unwrap() {
+                0 => Self::Archive {
+                    url: String::arbitrary(g),
+                    hashes: Hashes::arbitrary(g),
+                },
+                1 => Self::Mirror {
+                    host: String::arbitrary(g),
+                    port: u16::arbitrary(g),
+                },
+                _ => Self::Local,
+            }
+        }
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn round_trip_instantiate(config: Config, books: Books) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        writeable_db.db.close();
+
+        let read_only_db =
+            super::Database::<true, Config, Books>::open(test_db_dir, vec![], Default::default())
+                .unwrap();
+
+        read_only_db.config == config && read_only_db.books == books
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn round_trip_instantiate_with_paranoid_checks(config: Config, books: Books) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        writeable_db.db.close();
+
+        let mut options = rocksdb::Options::default();
+        options.set_paranoid_checks(true);
+
+        let read_only_db =
+            super::Database::<false, Config, Books>::open(test_db_dir, vec![], options).unwrap();
+
+        read_only_db.config == config && read_only_db.books == books
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn round_trip_instantiate_with_timeout(config: Config, books: Books) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        writeable_db.db.close();
+
+        let read_only_db = super::Database::<false, Config, Books>::open_with_timeout(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            std::time::Duration::from_secs(60),
+        )
+        .unwrap();
+
+        read_only_db.config == config && read_only_db.books == books
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn secondary_catches_up(config: Config, new_config: Config, books: Books) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let secondary_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        let mut secondary_db = super::Database::<false, Config, Books>::open_as_secondary(
+            &test_db_dir,
+            &secondary_dir,
+            vec![],
+            Default::default(),
+        )
+        .unwrap();
+
+        let before = secondary_db.config == config;
+
+        writeable_db.write_config(&new_config).unwrap();
+        secondary_db.try_catch_up_with_primary().unwrap();
+
+        before && secondary_db.config == new_config && secondary_db.books == books
+    }
+
+    #[quickcheck_macros::quickcheck]
+    #[allow(clippy::unit_cmp)]
+    fn round_trip_instantiate_with_unit(config: (), books: ()) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db =
+            super::Database::create(&test_db_dir, vec![], Default::default(), true, (), ())
+                .unwrap();
+
+        writeable_db.db.close();
+
+        let read_only_db =
+            super::Database::<true, (), ()>::open(test_db_dir, vec![], Default::default()).unwrap();
+
+        read_only_db.config == config && read_only_db.books == books
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn round_trip_instantiate_with_pessimistic_transactions(config: Config, books: Books) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            false,
+            config.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        writeable_db.db.close();
+
+        let read_only_db =
+            super::Database::<true, Config, Books>::open(test_db_dir, vec![], Default::default())
+                .unwrap();
+
+        read_only_db.config == config && read_only_db.books == books
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn round_trip_write(
+        config: Config,
+        books: Books,
+        new_config: Config,
+        new_books: Books,
+    ) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        writeable_db.db.close();
+
+        let writeable_db =
+            super::Database::<true, Config, Books>::open(&test_db_dir, vec![], Default::default())
+                .unwrap();
+
+        assert!(writeable_db.config == config && writeable_db.books == books);
+
+        writeable_db.write_config(&new_config).unwrap();
+        writeable_db.write_books(&new_books).unwrap();
+
+        writeable_db.read_config().unwrap() == new_config
+            && writeable_db.read_books().unwrap() == new_books
+    }
+
+    #[quickcheck_macros::quickcheck]
+    #[allow(deprecated)]
+    fn round_trip_write_with_pessimistic_transactions(
+        config: Config,
+        books: Books,
+        new_config: Config,
+        new_books: Books,
+    ) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            false,
+            config.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        writeable_db.db.close();
+
+        let writeable_db =
+            super::Database::<true, Config, Books>::open_with_pessimistic_transactions(
+                &test_db_dir,
+                vec![],
+                Default::default(),
+            )
+            .unwrap();
+
+        assert!(writeable_db.config == config && writeable_db.books == books);
+
+        writeable_db.write_config(&new_config).unwrap();
+        writeable_db.write_books(&new_books).unwrap();
+
+        writeable_db.read_config().unwrap() == new_config
+            && writeable_db.read_books().unwrap() == new_books
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn round_trip_write_with_enum(source: Source, books: Books, new_source: Source) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            source.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        writeable_db.db.close();
+
+        let writeable_db =
+            super::Database::<true, Source, Books>::open(&test_db_dir, vec![], Default::default())
+                .unwrap();
+
+        assert!(writeable_db.config == source && writeable_db.books == books);
+
+        writeable_db.write_config(&new_source).unwrap();
+
+        writeable_db.read_config().unwrap() == new_source
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            Source::Local,
+            (),
+        )
+        .unwrap();
+
+        let bincode_config = bincode::config::standard().with_big_endian();
+        let config_cf = writeable_db.db.handle(super::CONFIG_CF_NAME).unwrap();
+        let books_cf = writeable_db.db.handle(super::BOOKS_CF_NAME).unwrap();
+
+        let with_trailing_bytes = |mut bytes: Vec<u8>| {
+            bytes.extend([0, 1]);
+            bytes
+        };
+        let count = bincode::serde::encode_to_vec(10u64, bincode_config).unwrap();
+        let variant = writeable_db
+            .db
+            .get(config_cf, "_variant")
+            .unwrap()
+            .unwrap()
+            .to_vec();
+
+        writeable_db
+            .db
+            .put(config_cf, "count", with_trailing_bytes(count.clone()))
+            .unwrap();
+        writeable_db
+            .db
+            .put(books_cf, "count", with_trailing_bytes(count))
+            .unwrap();
+
+        let is_trailing_bytes = |error: &super::mapper::Error, expected: &str| {
+            matches!(
+                error,
+                super::mapper::Error::TrailingBytes { field, count: 2 } if field == expected
+            )
+        };
+
+        assert!(is_trailing_bytes(
+            &writeable_db.read_config_field::<u64>("count").unwrap_err(),
+            "count"
+        ));
+
+        match writeable_db.read_books_stream::<u64>().next() {
+            Some(Err(super::Error::Mapper(error))) => assert!(is_trailing_bytes(&error, "count")),
+            other => panic!("Unexpected result: {other:?}"),
+        }
+
+        writeable_db
+            .db
+            .put(config_cf, "_variant", with_trailing_bytes(variant))
+            .unwrap();
+
+        assert!(is_trailing_bytes(
+            &writeable_db.read_config().unwrap_err(),
+            "_variant"
+        ));
+    }
+
+    #[quickcheck_macros::quickcheck]
+    #[allow(deprecated)]
+    fn round_trip_write_with_enum_and_pessimistic_transactions(
+        source: Source,
+        books: Books,
+        new_source: Source,
+    ) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            false,
+            source.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        writeable_db.db.close();
+
+        let writeable_db =
+            super::Database::<true, Source, Books>::open_with_pessimistic_transactions(
+                &test_db_dir,
+                vec![],
+                Default::default(),
+            )
+            .unwrap();
+
+        assert!(writeable_db.config == source && writeable_db.books == books);
+
+        writeable_db.write_config(&new_source).unwrap();
+
+        writeable_db.read_config().unwrap() == new_source
+    }
+
+    #[quickcheck_macros::quickcheck]
+    #[allow(deprecated)]
+    fn round_trip_write_without_transactions(
+        config: Config,
+        books: Books,
+        new_config: Config,
+        new_books: Books,
+    ) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create_without_transactions(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            config.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        writeable_db.db.close();
+
+        let writeable_db = super::Database::<true, Config, Books>::open_without_transactions(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+        )
+        .unwrap();
+
+        assert!(writeable_db.config == config && writeable_db.books == books);
+
+        writeable_db.write_config(&new_config).unwrap();
+        writeable_db.write_books(&new_books).unwrap();
+
+        writeable_db.read_config().unwrap() == new_config
+            && writeable_db.read_books().unwrap() == new_books
+    }
+
+    /// Fails to serialize when the value is zero.
+    #[derive(Clone, Debug, Eq, PartialEq, serde_derive::Deserialize)]
+    struct Fallible(u64);
+
+    impl serde::Serialize for Fallible {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            if self.0 == 0 {
+                Err(serde::ser::Error::custom("zero"))
+            } else {
+                serializer.serialize_u64(self.0)
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, serde_derive::Deserialize, serde_derive::Serialize)]
+    struct Counts {
+        first: u64,
+        second: Fallible,
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn write_without_transactions_is_atomic() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let config = Counts {
+            first: 1,
+            second: Fallible(1),
+        };
+
+        let writeable_db = super::Database::create_without_transactions(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            config.clone(),
+            (),
+        )
+        .unwrap();
+
+        let result = writeable_db.write_config(&Counts {
+            first: 2,
+            second: Fallible(0),
+        });
+
+        assert!(matches!(result, Err(super::mapper::Error::Encoding(_))));
+        assert_eq!(writeable_db.read_config().unwrap(), config);
+    }
+
+    #[test]
+    fn mapper_error_conversion() {
+        fn create_and_update(
+            path: &std::path::Path,
+            config: &Counts,
+        ) -> Result<Counts, super::Error> {
+            let writeable_db = super::Database::create(
+                path,
+                vec![],
+                Default::default(),
+                true,
+                Counts {
+                    first: 1,
+                    second: Fallible(1),
+                },
+                (),
+            )?;
+
+            writeable_db.write_config(config)?;
+
+            Ok(writeable_db.read_config()?)
+        }
+
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let result = create_and_update(
+            test_db_dir.path(),
+            &Counts {
+                first: 2,
+                second: Fallible(0),
+            },
+        );
+
+        assert!(matches!(
+            result,
+            Err(super::Error::Mapper(super::mapper::Error::Encoding(_)))
+        ));
+    }
+
+    #[derive(
+        Clone,
+        Debug,
+        Default,
+        Eq,
+        PartialEq,
+        QuickCheck,
+        serde_derive::Deserialize,
+        serde_derive::Serialize,
+    )]
+    struct WideBooks {
+        a: u64,
+        b: u64,
+        c: u64,
+        d: u64,
+        e: u64,
+        f: u64,
+        g: u64,
+        h: u64,
+        i: u64,
+        last_scrape_ms: u64,
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn read_books_field(books: WideBooks) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let mut options = rocksdb::Options::default();
+        options.enable_statistics();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            options.clone(),
+            true,
+            (),
+            books.clone(),
+        )
+        .unwrap();
+
+        let before = options.get_ticker_count(rocksdb::statistics::Ticker::NumberKeysRead);
+        let last_scrape_ms = writeable_db
+            .read_books_field::<u64>("last_scrape_ms")
+            .unwrap();
+        let after = options.get_ticker_count(rocksdb::statistics::Ticker::NumberKeysRead);
+
+        let missing = writeable_db.read_books_field::<u64>("missing").unwrap();
+
+        last_scrape_ms == Some(books.last_scrape_ms) && after - before == 1 && missing.is_none()
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn read_books_with_multi_get(books: WideBooks) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let mut options = rocksdb::Options::default();
+        options.enable_statistics();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            options.clone(),
+            true,
+            (),
+            books.clone(),
+        )
+        .unwrap();
+
+        let ticker_counts = || {
+            (
+                options.get_ticker_count(rocksdb::statistics::Ticker::NumberKeysRead),
+                options.get_ticker_count(rocksdb::statistics::Ticker::NumberMultigetCalls),
+                options.get_ticker_count(rocksdb::statistics::Ticker::NumberMultigetKeysRead),
+            )
+        };
+
+        let (gets_before, multi_gets_before, multi_get_keys_before) = ticker_counts();
+        let read_books = writeable_db.read_books().unwrap();
+        let (gets_after, multi_gets_after, multi_get_keys_after) = ticker_counts();
+
+        read_books == books
+            && gets_after == gets_before
+            && multi_gets_after - multi_gets_before == 1
+            && multi_get_keys_after - multi_get_keys_before == 10
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn transaction_lock_timeout() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let mut transaction_db_options = rocksdb::TransactionDBOptions::default();
+        transaction_db_options.set_txn_lock_timeout(10);
+
+        let writeable_db = super::Database::create_with_transaction_db_options(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            transaction_db_options,
+            (),
+            (),
+        )
+        .unwrap();
+
+        let cf = super::Database::<true, (), ()>::books_cf(&writeable_db.db).unwrap();
+        let tx1 = writeable_db.db.transaction().unwrap();
+        let tx2 = writeable_db.db.transaction().unwrap();
+
+        tx1.put(cf, b"key", b"foo").unwrap();
+
+        let error = tx2.put(cf, b"key", b"bar").unwrap_err();
+
+        assert_eq!(error.kind(), rocksdb::ErrorKind::TimedOut);
+
+        tx1.commit().unwrap();
+        tx2.rollback().unwrap();
+
+        writeable_db.db.close();
+
+        let mut transaction_db_options = rocksdb::TransactionDBOptions::default();
+        transaction_db_options.set_txn_lock_timeout(10);
+
+        let writeable_db = super::Database::<true, (), ()>::open_with_transaction_db_options(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            transaction_db_options,
+        )
+        .unwrap();
+
+        let cf = super::Database::<true, (), ()>::books_cf(&writeable_db.db).unwrap();
+
+        assert_eq!(&*writeable_db.db.get(cf, b"key").unwrap().unwrap(), b"foo");
+    }
+
+    fn add_u64(
+        _key: &[u8],
+        existing: Option<&[u8]>,
+        operands: &rocksdb::MergeOperands,
+    ) -> Option<Vec<u8>> {
+        let existing = existing.map_or(0, |bytes| u64::from_be_bytes(bytes.try_into().unwrap()));
+
+        Some(
+            operands
+                .into_iter()
+                .fold(existing, |total, operand| {
+                    total + u64::from_be_bytes(operand.try_into().unwrap())
+                })
+                .to_be_bytes()
+                .to_vec(),
+        )
+    }
+
+    #[test]
+    fn merge_operators() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = crate::builder::DatabaseBuilder::new()
+            .path(test_db_dir.path())
+            .merge_operator("counters", add_u64)
+            .create()
+            .unwrap();
+
+        let cf = writeable_db.db.handle("counters").unwrap();
+
+        for increment in [1u64, 2, 3] {
+            writeable_db
+                .db
+                .merge(cf, b"count", increment.to_be_bytes())
+                .unwrap();
+        }
+
+        assert_eq!(
+            &*writeable_db.db.get(cf, b"count").unwrap().unwrap(),
+            6u64.to_be_bytes()
+        );
+        assert!(matches!(
+            writeable_db.db.merge(
+                super::Database::<true, (), ()>::books_cf(&writeable_db.db).unwrap(),
+                b"count",
+                []
+            ),
+            Err(super::Error::NoMergeOperator)
+        ));
+
+        let tx = writeable_db.transaction().unwrap();
+        tx.merge(cf, b"count", 4u64.to_be_bytes()).unwrap();
+        assert!(matches!(
+            tx.merge(
+                super::Database::<true, (), ()>::books_cf(&writeable_db.db).unwrap(),
+                b"count",
+                []
+            ),
+            Err(super::Error::NoMergeOperator)
+        ));
+        tx.commit().unwrap();
+
+        assert_eq!(
+            &*writeable_db.db.get(cf, b"count").unwrap().unwrap(),
+            10u64.to_be_bytes()
+        );
+
+        writeable_db.db.close();
+
+        let writeable_db = crate::builder::DatabaseBuilder::new()
+            .path(test_db_dir.path())
+            .merge_operator("counters", add_u64)
+            .open::<(), ()>()
+            .unwrap();
+
+        let cf = writeable_db.db.handle("counters").unwrap();
+
+        writeable_db
+            .db
+            .merge(cf, b"count", 10u64.to_be_bytes())
+            .unwrap();
+
+        assert_eq!(
+            &*writeable_db.db.get(cf, b"count").unwrap().unwrap(),
+            20u64.to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn ttl() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let books = Books {
+            last_scrape_ms: 1,
+            region: "foo".to_string(),
+        };
+        let cfs = || {
+            vec![
+                rocksdb::ColumnFamilyDescriptor::new("transient", Default::default()),
+                rocksdb::ColumnFamilyDescriptor::new("durable", Default::default()),
+            ]
+        };
+
+        let writeable_db = crate::builder::DatabaseBuilder::new()
+            .path(test_db_dir.path())
+            .column_families(cfs())
+            .ttl("transient", 1)
+            .config(books.clone())
+            .books(books.clone())
+            .create()
+            .unwrap();
+
+        let transient_cf = writeable_db.db.handle("transient").unwrap();
+        let durable_cf = writeable_db.db.handle("durable").unwrap();
+
+        writeable_db.db.put(transient_cf, b"foo", b"bar").unwrap();
+        writeable_db.db.put(durable_cf, b"foo", b"bar").unwrap();
+
+        writeable_db.db.close();
+
+        let writeable_db = crate::builder::DatabaseBuilder::new()
+            .path(test_db_dir.path())
+            .column_families(cfs())
+            .ttl("transient", 1)
+            .open::<Books, Books>()
+            .unwrap();
+
+        let transient_cf = writeable_db.db.handle("transient").unwrap();
+        let durable_cf = writeable_db.db.handle("durable").unwrap();
+
+        assert_eq!(
+            &*writeable_db.db.get(transient_cf, b"foo").unwrap().unwrap(),
+            b"bar"
+        );
+
+        std::thread::sleep(std::time::Duration::from_secs(3));
+
+        for cf_name in [
+            "transient",
+            "durable",
+            super::CONFIG_CF_NAME,
+            super::BOOKS_CF_NAME,
+        ] {
+            writeable_db
+                .db
+                .compact(writeable_db.db.handle(cf_name).unwrap())
+                .unwrap();
+        }
+
+        assert!(writeable_db.db.get(transient_cf, b"foo").unwrap().is_none());
+        assert_eq!(
+            &*writeable_db.db.get(durable_cf, b"foo").unwrap().unwrap(),
+            b"bar"
+        );
+        assert_eq!(writeable_db.read_config().unwrap(), books);
+        assert_eq!(writeable_db.read_books().unwrap(), books);
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn universal_compaction(config: Config, books: Books, new_books: Books) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let cfs = || {
+            let mut options = rocksdb::Options::default();
+            options.set_compaction_style(rocksdb::DBCompactionStyle::Universal);
+
+            super::managed_cf_descriptors(&options)
+        };
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            cfs(),
+            Default::default(),
+            true,
+            config.clone(),
+            books,
+        )
+        .unwrap();
+
+        writeable_db.write_books(&new_books).unwrap();
+        writeable_db.flush().unwrap();
+        writeable_db.db.close();
+
+        let read_only_db =
+            super::Database::<false, Config, Books>::open(&test_db_dir, cfs(), Default::default())
+                .unwrap();
+
+        read_only_db.config == config && read_only_db.books == new_books
+    }
+
+    #[test]
+    fn managed_cf_options() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let books = Books {
+            last_scrape_ms: 1,
+            region: "foo".repeat(1000),
+        };
+        let cfs = || {
+            let mut books_options = rocksdb::Options::default();
+            books_options.set_compression_type(rocksdb::DBCompressionType::Zstd);
+
+            vec![rocksdb::ColumnFamilyDescriptor::new(
+                super::BOOKS_CF_NAME,
+                books_options,
+            )]
+        };
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            cfs(),
+            Default::default(),
+            true,
+            (),
+            books.clone(),
+        )
+        .unwrap();
+
+        writeable_db.db.close();
+
+        let admin = super::Database::<true, (), Books>::admin(&test_db_dir, cfs()).unwrap();
+        admin.flush().unwrap();
+
+        // The repetitive books value would take at least 3,000 bytes uncompressed.
+        assert!(admin.total_size().unwrap() < 3000);
+
+        drop(admin);
+
+        let read_only_db =
+            super::Database::<false, (), Books>::open(&test_db_dir, cfs(), Default::default())
+                .unwrap();
+
+        assert_eq!(read_only_db.books, books);
+    }
+
+    #[test]
+    fn open_without_managed_cfs() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+
+        let db = rocksdb::DB::open(&options, &test_db_dir).unwrap();
+        db.put(b"foo", b"bar").unwrap();
+        drop(db);
+
+        let is_missing_config_cf = |result: Result<(), super::Error>| {
+            matches!(
+                result,
+                Err(super::Error::Mapper(super::mapper::Error::MissingColumnFamily(cf_name)))
+                    if cf_name == super::CONFIG_CF_NAME
+            )
+        };
+
+        assert!(is_missing_config_cf(
+            super::Database::<false, (), ()>::open(&test_db_dir, vec![], Default::default())
+                .map(|_| ())
+        ));
+        assert!(is_missing_config_cf(
+            super::Database::<true, (), ()>::open(&test_db_dir, vec![], Default::default())
+                .map(|_| ())
+        ));
+        assert!(is_missing_config_cf(
+            super::Database::<true, (), ()>::admin(&test_db_dir, vec![]).map(|_| ())
+        ));
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn flush(config: Config, books: Books) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let mut options = rocksdb::Options::default();
+        options.enable_statistics();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            options.clone(),
+            true,
+            config.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        let before = options.get_ticker_count(rocksdb::statistics::Ticker::FlushWriteBytes);
+        writeable_db.flush().unwrap();
+        let after = options.get_ticker_count(rocksdb::statistics::Ticker::FlushWriteBytes);
+
+        let read_only_db =
+            super::Database::<false, Config, Books>::open(&test_db_dir, vec![], Default::default())
+                .unwrap();
+
+        after > before && read_only_db.config == config && read_only_db.books == books
+    }
+
+    #[test]
+    fn flush_unsupported() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db =
+            super::Database::create(&test_db_dir, vec![], Default::default(), false, (), ())
+                .unwrap();
+
+        assert!(matches!(
+            writeable_db.flush(),
+            Err(super::Error::Unsupported)
+        ));
+
+        writeable_db.db.close();
+
+        let read_only_db =
+            super::Database::<false, (), ()>::open(&test_db_dir, vec![], Default::default())
+                .unwrap();
+
+        assert!(matches!(
+            read_only_db.db.flush(),
+            Err(super::Error::Unsupported)
+        ));
+    }
+
+    #[test]
+    fn compact_range() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let test_cf = rocksdb::ColumnFamilyDescriptor::new("test", Default::default());
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![test_cf],
+            Default::default(),
+            true,
+            (),
+            (),
+        )
+        .unwrap();
+
+        let cf = writeable_db.db.handle("test").unwrap();
+        let sst_size = || {
+            writeable_db
+                .db
+                .property_int(cf, "rocksdb.total-sst-files-size")
+                .unwrap()
+                .unwrap()
+        };
+
+        for i in 0..1000u64 {
+            writeable_db.db.put(cf, i.to_be_bytes(), [0; 100]).unwrap();
+        }
+
+        writeable_db.flush().unwrap();
+
+        for i in 0..1000u64 {
+            writeable_db.db.delete(cf, i.to_be_bytes()).unwrap();
+        }
+
+        writeable_db.flush().unwrap();
+
+        let before = sst_size();
+
+        writeable_db
+            .compact_range::<&[u8], &[u8]>("test", None, None)
+            .unwrap();
+
+        assert!(sst_size() < before);
+        assert!(matches!(
+            writeable_db.compact_range::<&[u8], &[u8]>("missing", None, None),
+            Err(super::Error::UnknownColumnFamily(_))
+        ));
+
+        writeable_db.db.close();
+
+        let pessimistic_test_db_dir = tempfile::tempdir().unwrap();
+        let writeable_db = super::Database::create(
+            &pessimistic_test_db_dir,
+            vec![],
+            Default::default(),
+            false,
+            (),
+            (),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            writeable_db.compact_range::<&[u8], &[u8]>(super::CONFIG_CF_NAME, None, None),
+            Err(super::Error::Unsupported)
+        ));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn open_with_mode() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let books = Books {
+            last_scrape_ms: 1,
+            region: "foo".to_string(),
+        };
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            (),
+            books.clone(),
+        )
+        .unwrap();
+
+        writeable_db.db.close();
+
+        for (mode, optimistic, pessimistic) in [
+            (super::OpenMode::OptimisticTransactions, true, false),
+            (super::OpenMode::PessimisticTransactions, false, true),
+            (super::OpenMode::WithoutTransactions, false, false),
+        ] {
+            let writeable_db = super::Database::<true, (), Books>::open_with_mode(
+                &test_db_dir,
+                vec![],
+                Default::default(),
+                mode,
+            )
+            .unwrap();
+
+            assert_eq!(writeable_db.db.as_optimistic().is_some(), optimistic);
+            assert_eq!(writeable_db.db.as_pessimistic().is_some(), pessimistic);
+            assert_eq!(writeable_db.books, books);
+
+            writeable_db.write_books(&books).unwrap();
+            writeable_db.db.close();
+        }
+    }
+
+    #[test]
+    fn open_read_only_with_log_file() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        // The writes are only in the write-ahead log while the writeable handle is open.
+        let writeable_db =
+            super::Database::create(&test_db_dir, vec![], Default::default(), true, (), ())
+                .unwrap();
+
+        assert!(super::Database::<false, (), ()>::open_read_only(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            false
+        )
+        .is_ok());
+        assert!(matches!(
+            super::Database::<false, (), ()>::open_read_only(
+                &test_db_dir,
+                vec![],
+                Default::default(),
+                true
+            ),
+            Err(super::Error::Db(_))
+        ));
+
+        writeable_db.db.close();
+    }
+
+    #[derive(Debug, Eq, PartialEq, serde_derive::Deserialize)]
+    struct ReducedConfig {
+        case_sensitive: bool,
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn raw_entries(config: Config, books: Books) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        let endianness = writeable_db.db.endianness();
+
+        writeable_db.raw_config_entries().unwrap()
+            == vec![
+                (
+                    "case_sensitive".to_string(),
+                    endianness.encode(&config.case_sensitive).unwrap(),
+                ),
+                (
+                    "hashes".to_string(),
+                    endianness.encode(&config.hashes).unwrap(),
+                ),
+            ]
+            && writeable_db.raw_books_entries().unwrap()
+                == vec![
+                    (
+                        "last_scrape_ms".to_string(),
+                        endianness.encode(&books.last_scrape_ms).unwrap(),
+                    ),
+                    (
+                        "region".to_string(),
+                        endianness.encode(&books.region).unwrap(),
+                    ),
+                ]
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn config_size_bytes(config: Config, books: Books) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        let endianness = writeable_db.db.endianness();
+        let config_size = endianness.encode(&config.hashes).unwrap().len()
+            + endianness.encode(&config.case_sensitive).unwrap().len();
+        let books_size = endianness.encode(&books.last_scrape_ms).unwrap().len()
+            + endianness.encode(&books.region).unwrap().len();
+
+        writeable_db.config_size_bytes().unwrap() == config_size as u64
+            && writeable_db.books_size_bytes().unwrap() == books_size as u64
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn stored_config_keys(config: Config) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            (),
+        )
+        .unwrap();
+
+        writeable_db.db.close();
+
+        let read_only_db = super::Database::<false, ReducedConfig, ()>::open(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+        )
+        .unwrap();
+
+        read_only_db.config.case_sensitive == config.case_sensitive
+            && read_only_db.stored_config_keys().unwrap() == vec!["case_sensitive", "hashes"]
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn prune_config(config: Config) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let db = super::Database::<true, Config, ()>::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            (),
+        )
+        .unwrap();
+
+        db.write_config_field("obsolete", &1u64).unwrap();
+
+        let before = db.stored_config_keys().unwrap();
+        let removed = db.prune_config(&["hashes", "case_sensitive"]).unwrap();
+        let after = db.stored_config_keys().unwrap();
+
+        before == vec!["case_sensitive", "hashes", "obsolete"]
+            && removed == 1
+            && after == vec!["case_sensitive", "hashes"]
+            && db.read_config().unwrap() == config
+            && db.stored_config_type().unwrap().is_some()
+    }
+
+    #[quickcheck_macros::quickcheck]
+    #[allow(deprecated)]
+    fn create_in_memory(config: Config, books: Books, new_books: Books) -> bool {
+        let db = super::Database::create_in_memory(config.clone(), books.clone()).unwrap();
+
+        let before = db.read_config().unwrap() == config && db.read_books().unwrap() == books;
+
+        db.write_books(&new_books).unwrap();
+
+        before && db.read_books().unwrap() == new_books
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq, serde_derive::Deserialize, serde_derive::Serialize)]
+    struct NewtypeConfig(Config);
+
+    #[derive(Clone, Debug, Eq, PartialEq, serde_derive::Deserialize, serde_derive::Serialize)]
+    struct NestedNewtypeBooks(NewtypeBooks);
+
+    #[derive(Clone, Debug, Eq, PartialEq, serde_derive::Deserialize, serde_derive::Serialize)]
+    struct NewtypeBooks(Books);
+
+    #[quickcheck_macros::quickcheck]
+    fn newtype_round_trip(config: Config, books: Books) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let config = NewtypeConfig(config);
+        let books = NestedNewtypeBooks(NewtypeBooks(books));
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        writeable_db.db.close();
+
+        let read_only_db = super::Database::<false, NewtypeConfig, NestedNewtypeBooks>::open(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+        )
+        .unwrap();
+
+        let unwrapped_db =
+            super::Database::<false, Config, Books>::open(&test_db_dir, vec![], Default::default());
+
+        read_only_db.config == config
+            && read_only_db.books == books
+            && unwrapped_db.is_ok_and(|db| db.config == config.0 && db.books == books.0 .0)
+    }
+
+    #[test]
+    fn debug() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db =
+            super::Database::create(&test_db_dir, vec![], Default::default(), true, (), ())
+                .unwrap();
+
+        assert_eq!(
+            format!("{writeable_db:?}"),
+            r#"Database { writeable: true, db: Db { mode: "OptimisticTransaction", cf_names: ["_config", "_books"] }, .. }"#
+        );
+
+        writeable_db.db.close();
+
+        let read_only_db =
+            super::Database::<false, (), ()>::open(test_db_dir, vec![], Default::default())
+                .unwrap();
+
+        assert!(format!("{read_only_db:?}").contains(r#"mode: "ReadOnly""#));
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn stored_type_names(config: Config, books: Books) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config,
+            books,
+        )
+        .unwrap();
+
+        writeable_db.db.close();
+
+        let read_only_db =
+            super::Database::<false, Config, Books>::open(test_db_dir, vec![], Default::default())
+                .unwrap();
+
+        read_only_db.stored_config_type().unwrap()
+            == Some(std::any::type_name::<Config>().to_string())
+            && read_only_db.stored_books_type().unwrap()
+                == Some(std::any::type_name::<Books>().to_string())
+    }
+
+    #[test]
+    fn failed_create_records_no_type_names() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let books = std::collections::BTreeMap::from([(super::TYPE_NAME_KEY.to_string(), 0u64)]);
+
+        assert!(matches!(
+            super::Database::create(
+                &test_db_dir,
+                vec![],
+                Default::default(),
+                true,
+                Config::default(),
+                books,
+            ),
+            Err(super::Error::Mapper(super::mapper::Error::ReservedKey(_)))
+        ));
+
+        let db = rocksdb::DB::open_cf_for_read_only(
+            &Default::default(),
+            &test_db_dir,
+            [super::CONFIG_CF_NAME, super::BOOKS_CF_NAME],
+            false,
+        )
+        .unwrap();
+
+        for cf_name in [super::CONFIG_CF_NAME, super::BOOKS_CF_NAME] {
+            let cf = db.cf_handle(cf_name).unwrap();
+
+            assert_eq!(db.get_cf(cf, super::TYPE_NAME_KEY).unwrap(), None);
+        }
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn write_config_field(config: Config, books: Books, case_sensitive: bool) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        writeable_db
+            .write_config_field("case_sensitive", &case_sensitive)
+            .unwrap();
+
+        let expected = Config {
+            case_sensitive,
+            ..config
+        };
+
+        writeable_db.read_config().unwrap() == expected
+            && writeable_db.read_books().unwrap() == books
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn on_write(config: Config, books: Books, new_config: Config) -> bool {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config,
+            books,
+        )
+        .unwrap();
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let total = Arc::new(AtomicUsize::new(0));
+        let hook_calls = calls.clone();
+        let hook_total = total.clone();
+
+        writeable_db.set_on_write(Some(Box::new(move |cf_name, bytes| {
+            assert_eq!(cf_name, super::CONFIG_CF_NAME);
+            hook_calls.fetch_add(1, Ordering::SeqCst);
+            hook_total.fetch_add(bytes, Ordering::SeqCst);
+        })));
+
+        writeable_db.write_config(&new_config).unwrap();
+
+        let expected = super::Endianness::Big
+            .encode(&new_config.hashes)
+            .unwrap()
+            .len()
+            + super::Endianness::Big
+                .encode(&new_config.case_sensitive)
+                .unwrap()
+                .len();
+
+        calls.load(Ordering::SeqCst) == 1 && total.load(Ordering::SeqCst) == expected
+    }
+
+    #[test]
+    fn on_write_removes_itself() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            Config::default(),
+            (),
+        )
+        .unwrap();
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let hook_calls = calls.clone();
+        let db = writeable_db.db.clone();
+
+        writeable_db.set_on_write(Some(Box::new(move |_, _| {
+            hook_calls.fetch_add(1, Ordering::SeqCst);
+            db.set_on_write(None);
+        })));
+
+        writeable_db.write_config(&Config::default()).unwrap();
+        writeable_db.write_config(&Config::default()).unwrap();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn write_in_transaction(
+        config: Config,
+        books: Books,
+        new_config: Config,
+        new_books: Books,
+    ) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        let tx = writeable_db.transaction().unwrap();
+        writeable_db.write_config_in(&tx, &new_config).unwrap();
+        writeable_db.write_books_in(&tx, &new_books).unwrap();
+        std::mem::drop(tx);
+
+        assert!(
+            writeable_db.read_config().unwrap() == config
+                && writeable_db.read_books().unwrap() == books
+        );
+
+        let tx = writeable_db.transaction().unwrap();
+        writeable_db.write_config_in(&tx, &new_config).unwrap();
+        writeable_db.write_books_in(&tx, &new_books).unwrap();
+        tx.commit().unwrap();
+
+        writeable_db.read_config().unwrap() == new_config
+            && writeable_db.read_books().unwrap() == new_books
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn round_trip_flush_on_drop(config: Config, books: Books, new_books: Books) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            books,
+        )
+        .unwrap();
+
+        writeable_db.set_flush_on_drop(true);
+        writeable_db.write_books(&new_books).unwrap();
+
+        std::mem::drop(writeable_db);
+
+        // Remove the write-ahead log so that only flushed data is visible.
+        for entry in std::fs::read_dir(&test_db_dir).unwrap() {
+            let path = entry.unwrap().path();
+
+            if path.extension().is_some_and(|extension| extension == "log") {
+                std::fs::remove_file(path).unwrap();
+            }
+        }
+
+        let read_only_db =
+            super::Database::<false, Config, Books>::open(test_db_dir, vec![], Default::default())
+                .unwrap();
+
+        read_only_db.config == config && read_only_db.books == new_books
+    }
+
+    #[test]
+    fn delete_files_in_range() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let test_cf = || rocksdb::ColumnFamilyDescriptor::new("test", Default::default());
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![test_cf()],
+            Default::default(),
+            true,
+            (),
+            (),
+        )
+        .unwrap();
+
+        writeable_db.db.close();
+
+        let admin = super::Database::<true, (), ()>::admin(&test_db_dir, vec![test_cf()]).unwrap();
+        let cf = admin.underlying.cf_handle("test").unwrap();
+
+        // Write three chunks of keys, each compacted into its own file outside level 0.
+        for chunk in 0..3u64 {
+            for i in chunk * 1000..(chunk + 1) * 1000 {
+                admin
+                    .underlying
+                    .put_cf(cf, i.to_be_bytes(), [0; 100])
+                    .unwrap();
+            }
+
+            admin.flush().unwrap();
+            admin.compact().unwrap();
+        }
+
+        let before = admin.total_size().unwrap();
+
+        admin
+            .delete_files_in_range("test", 1000u64.to_be_bytes(), 1999u64.to_be_bytes())
+            .unwrap();
+
+        let after = admin.total_size().unwrap();
+
+        assert!(after < before);
+        assert!(matches!(
+            admin.delete_files_in_range("missing", [0], [1]),
+            Err(super::Error::UnknownColumnFamily(cf_name)) if cf_name == "missing"
+        ));
+        assert!(admin
+            .underlying
+            .get_cf(cf, 500u64.to_be_bytes())
+            .unwrap()
+            .is_some());
+        assert!(admin
+            .underlying
+            .get_cf(cf, 2500u64.to_be_bytes())
+            .unwrap()
+            .is_some());
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn checkpoint_and_verify(config: Config, books: Books) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let checkpoint_dir = tempfile::tempdir().unwrap();
+        let checkpoint_path = checkpoint_dir.path().join("checkpoint");
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        writeable_db
+            .checkpoint_and_verify(&checkpoint_path)
+            .unwrap();
+
+        let checkpoint_db = super::Database::<false, Config, Books>::open(
+            &checkpoint_path,
+            vec![],
+            Default::default(),
+        )
+        .unwrap();
+
+        checkpoint_db.config == config && checkpoint_db.books == books
+    }
+
+    #[test]
+    fn books_entries_with_options() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db =
+            super::Database::create(&test_db_dir, vec![], Default::default(), true, (), ())
+                .unwrap();
+
+        let mut write_options = rocksdb::WriteOptions::default();
+        write_options.set_low_pri(true);
+        write_options.set_no_slowdown(true);
+
+        let result = writeable_db.insert_books_entries_with_options(
+            (0..100).map(|i| (format!("key-{i}"), i as u64)),
+            write_options,
+        );
+
+        match result {
+            Ok(()) => {
+                assert_eq!(writeable_db.read_books_stream::<u64>().count(), 100);
+            }
+            Err(error) => {
+                assert!(matches!(error, super::Error::WouldBlock));
+            }
+        }
+    }
+
+    #[test]
+    fn open_not_writable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db =
+            super::Database::create(&test_db_dir, vec![], Default::default(), true, (), ())
+                .unwrap();
+
+        writeable_db.db.close();
+
+        std::fs::set_permissions(&test_db_dir, std::fs::Permissions::from_mode(0o555)).unwrap();
+
+        // Some environments (for example when running as root) ignore directory permissions.
+        let probe_path = test_db_dir.path().join("probe");
+        let simulated = std::fs::File::create(&probe_path).is_err();
+        let _ = std::fs::remove_file(&probe_path);
+
+        if simulated {
+            let result =
+                super::Database::<true, (), ()>::open(&test_db_dir, vec![], Default::default());
+
+            assert!(matches!(
+                result,
+                Err(super::Error::NotWritable { path }) if path == test_db_dir.path()
+            ));
+        }
+
+        std::fs::set_permissions(&test_db_dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert!(
+            super::Database::<true, (), ()>::open(&test_db_dir, vec![], Default::default()).is_ok()
+        );
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn round_trip_table(config: Config, books: Books, table: Books) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let test_cf = || rocksdb::ColumnFamilyDescriptor::new("test", Default::default());
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![test_cf()],
+            Default::default(),
+            true,
+            config.clone(),
+            books.clone(),
+        )
+        .unwrap();
+
+        writeable_db.write_table("test", &table).unwrap();
+        assert!(matches!(
+            writeable_db.write_table("missing", &table),
+            Err(super::Error::UnknownColumnFamily(_))
+        ));
+
+        writeable_db.db.close();
+
+        let read_only_db = super::Database::<false, Config, Books>::open(
+            &test_db_dir,
+            vec![test_cf()],
+            Default::default(),
+        )
+        .unwrap();
+
+        read_only_db.read_table::<Books>("test").unwrap() == table
+            && read_only_db.config == config
+            && read_only_db.books == books
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn round_trip_little_endian(config: Config, books: Books, new_books: Books) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = crate::builder::DatabaseBuilder::new()
+            .path(test_db_dir.path())
+            .endianness(super::Endianness::Little)
+            .config(config.clone())
+            .books(books.clone())
+            .create()
+            .unwrap();
+
+        writeable_db.write_books(&new_books).unwrap();
+        writeable_db.db.close();
+
+        let read_only_db =
+            super::Database::<false, Config, Books>::open(&test_db_dir, vec![], Default::default())
+                .unwrap();
+
+        let cf = read_only_db.db.handle(super::BOOKS_CF_NAME).unwrap();
+        let stored = read_only_db.db.get(cf, "last_scrape_ms").unwrap().unwrap();
+        let expected = super::Endianness::Little
+            .encode(&new_books.last_scrape_ms)
+            .unwrap();
+
+        read_only_db.endianness() == super::Endianness::Little
+            && stored.as_ref() == expected
+            && read_only_db.config == config
+            && read_only_db.books == new_books
+    }
+
+    #[test]
+    fn schema_version() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let legacy_test_db_dir = tempfile::tempdir().unwrap();
+
+        crate::builder::DatabaseBuilder::new()
+            .path(test_db_dir.path())
+            .schema_version(3)
+            .create()
+            .unwrap()
+            .db
+            .close();
+
+        super::Database::create(
+            &legacy_test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            (),
+            (),
+        )
+        .unwrap()
+        .db
+        .close();
+
+        let open = |path: &std::path::Path, expected| {
+            crate::builder::DatabaseBuilder::new()
+                .path(path)
+                .schema_version(expected)
+                .open::<(), ()>()
+        };
+
+        assert_eq!(
+            open(test_db_dir.path(), 3)
+                .unwrap()
+                .schema_version()
+                .unwrap(),
+            3
+        );
+        assert!(matches!(
+            open(test_db_dir.path(), 4),
+            Err(super::Error::SchemaMismatch {
+                expected: 4,
+                found: 3
+            })
+        ));
+        assert!(open(legacy_test_db_dir.path(), 0).is_ok());
+        assert!(matches!(
+            open(legacy_test_db_dir.path(), 1),
+            Err(super::Error::SchemaMismatch {
+                expected: 1,
+                found: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn schema_version_checked_before_decoding() {
+        #[derive(Debug, serde_derive::Deserialize)]
+        struct NewConfig {
+            #[allow(dead_code)]
+            limit: u64,
+        }
+
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        crate::builder::DatabaseBuilder::new()
+            .path(test_db_dir.path())
+            .schema_version(1)
+            .config(Config::default())
+            .create()
+            .unwrap()
+            .db
+            .close();
+
+        assert!(matches!(
+            crate::builder::DatabaseBuilder::new()
+                .path(test_db_dir.path())
+                .schema_version(2)
+                .open::<NewConfig, ()>(),
+            Err(super::Error::SchemaMismatch {
+                expected: 2,
+                found: 1
+            })
+        ));
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn admin_checkpoint(config: Config, books: Books) -> bool {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let checkpoint_dir = tempfile::tempdir().unwrap();
+        let checkpoint_path = checkpoint_dir.path().join("checkpoint");
+
+        super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            books.clone(),
+        )
+        .unwrap()
+        .db
+        .close();
+
+        let admin = super::Database::<true, Config, Books>::admin(&test_db_dir, vec![]).unwrap();
+        admin.checkpoint(&checkpoint_path).unwrap();
+
+        assert!(matches!(
+            admin.checkpoint(&checkpoint_path),
+            Err(super::Error::CheckpointTargetExists { path }) if path == checkpoint_path
+        ));
+
+        let checkpoint_db = super::Database::<false, Config, Books>::open(
+            &checkpoint_path,
+            vec![],
+            Default::default(),
+        )
+        .unwrap();
+
+        checkpoint_db.config == config && checkpoint_db.books == books
+    }
+
+    #[test]
+    fn admin_statistics() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db =
+            super::Database::create(&test_db_dir, vec![], Default::default(), true, (), ())
+                .unwrap();
+
+        writeable_db
+            .insert_books_entries((0..10).map(|i| (format!("key-{i}"), i as u64)))
+            .unwrap();
+        writeable_db.db.close();
+
+        let admin = super::Database::<true, (), ()>::admin(&test_db_dir, vec![]).unwrap();
+
+        assert_eq!(admin.statistics_string(), None);
+
+        drop(admin);
+
+        let mut options = super::Options::default();
+        options.enable_statistics();
+
+        let admin =
+            super::Database::<true, (), ()>::admin_with_options(&test_db_dir, vec![], options)
+                .unwrap();
+        let cf = admin.underlying.cf_handle(super::BOOKS_CF_NAME).unwrap();
+
+        for i in 0..10 {
+            assert!(admin
+                .underlying
+                .get_cf(cf, format!("key-{i}"))
+                .unwrap()
+                .is_some());
+        }
+
+        assert!(!admin.statistics_string().unwrap().is_empty());
+        assert_eq!(
+            admin.statistics_ticker(rocksdb::statistics::Ticker::NumberKeysRead),
+            10
+        );
+    }
+
+    #[test]
+    fn clear_cf() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db =
+            super::Database::create(&test_db_dir, vec![], Default::default(), true, (), ())
+                .unwrap();
+
+        writeable_db
+            .insert_books_entries((0..10).map(|i| (format!("key-{i}"), i as u64)))
+            .unwrap();
+        writeable_db.db.close();
+
+        let admin = super::Database::<true, (), ()>::admin(&test_db_dir, vec![]).unwrap();
+
+        admin.clear_cf(super::BOOKS_CF_NAME).unwrap();
+        admin.clear_cf("missing").unwrap();
+
+        let cf = admin.underlying.cf_handle(super::BOOKS_CF_NAME).unwrap();
+
+        assert_eq!(
+            admin
+                .underlying
+                .iterator_cf(cf, rocksdb::IteratorMode::Start)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn cf_options_summary() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let cfs = || {
+            let mut block_options = rocksdb::BlockBasedOptions::default();
+            block_options.set_block_size(8192);
+
+            let mut books_options = rocksdb::Options::default();
+            books_options.set_compression_type(rocksdb::DBCompressionType::Zstd);
+            books_options.set_block_based_table_factory(&block_options);
+
+            vec![rocksdb::ColumnFamilyDescriptor::new(
+                super::BOOKS_CF_NAME,
+                books_options,
+            )]
+        };
+
+        super::Database::create(&test_db_dir, cfs(), Default::default(), true, (), ())
+            .unwrap()
+            .db
+            .close();
+
+        let admin = super::Database::<true, (), ()>::admin(&test_db_dir, cfs()).unwrap();
+
+        assert_eq!(
+            admin.cf_options_summary(super::BOOKS_CF_NAME).as_deref(),
+            Some("compression=kZSTD; block_size=8192")
+        );
+        assert_eq!(
+            admin.cf_options_summary(super::CONFIG_CF_NAME).as_deref(),
+            Some("compression=kSnappyCompression; block_size=4096")
+        );
+        assert_eq!(admin.cf_options_summary("missing"), None);
+    }
+
+    #[test]
+    fn admin_properties() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db =
+            super::Database::create(&test_db_dir, vec![], Default::default(), true, (), ())
+                .unwrap();
+
+        writeable_db
+            .insert_books_entries((0..10).map(|i| (format!("key-{i}"), i as u64)))
+            .unwrap();
+        writeable_db.db.close();
+
+        let admin = super::Database::<true, (), ()>::admin(&test_db_dir, vec![]).unwrap();
+
+        let estimate = admin
+            .property_int(super::BOOKS_CF_NAME, "rocksdb.estimate-num-keys")
+            .unwrap()
+            .unwrap();
+
+        assert!(estimate >= 10);
+        assert!(admin
+            .property_value(super::BOOKS_CF_NAME, "rocksdb.estimate-num-keys")
+            .unwrap()
+            .is_some());
+        assert!(admin
+            .property_int("missing", "rocksdb.estimate-num-keys")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn admin_verify_checksums() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db =
+            super::Database::create(&test_db_dir, vec![], Default::default(), true, (), ())
+                .unwrap();
+
+        writeable_db
+            .insert_books_entries((0..100).map(|i| (format!("key-{i}"), i as u64)))
+            .unwrap();
+        writeable_db.db.close();
+
+        let admin = super::Database::<true, (), ()>::admin(&test_db_dir, vec![]).unwrap();
+        admin.flush().unwrap();
+
+        assert!(admin.verify_checksums().is_ok());
+
+        let sst_path = admin
+            .live_files()
+            .unwrap()
+            .into_iter()
+            .find(|live_file| live_file.column_family_name == super::BOOKS_CF_NAME)
+            .map(|live_file| {
+                test_db_dir
+                    .path()
+                    .join(live_file.name.trim_start_matches('/'))
+            })
+            .unwrap();
+        drop(admin);
+
+        // Corrupt the first data block.
+        let mut bytes = std::fs::read(&sst_path).unwrap();
+        bytes[16] ^= 0xff;
+        std::fs::write(&sst_path, bytes).unwrap();
+
+        let admin = super::Database::<true, (), ()>::admin(&test_db_dir, vec![]).unwrap();
+
+        assert_eq!(
+            admin.verify_checksums().unwrap_err().kind(),
+            rocksdb::ErrorKind::Corruption
+        );
+    }
+
+    #[test]
+    fn admin_live_files() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db =
+            super::Database::create(&test_db_dir, vec![], Default::default(), true, (), ())
+                .unwrap();
+
+        writeable_db
+            .insert_books_entries((0..10).map(|i| (format!("key-{i}"), i as u64)))
+            .unwrap();
+        writeable_db.db.close();
+
+        let admin = super::Database::<true, (), ()>::admin(&test_db_dir, vec![]).unwrap();
+        admin.flush().unwrap();
+
+        let live_files = admin.live_files().unwrap();
+        let books_file = live_files
+            .iter()
+            .find(|live_file| live_file.column_family_name == super::BOOKS_CF_NAME)
+            .unwrap();
+
+        assert!(books_file.size > 0);
+        assert!(books_file.start_key.is_some() && books_file.end_key.is_some());
+        assert!(live_files.is_sorted_by_key(|live_file| &live_file.column_family_name));
+    }
+
+    #[test]
+    fn compare() {
+        let config = Config {
+            hashes: Hashes::Md5Only,
+            case_sensitive: false,
+        };
+        let books = Books {
+            last_scrape_ms: 1_000,
+            region: "us".to_string(),
+        };
+
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let copy_db_dir = tempfile::tempdir().unwrap();
+
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            config.clone(),
+            books,
+        )
+        .unwrap();
+
+        writeable_db.db.close();
+
+        for entry in std::fs::read_dir(&test_db_dir).unwrap() {
+            let path = entry.unwrap().path();
+            std::fs::copy(&path, copy_db_dir.path().join(path.file_name().unwrap())).unwrap();
+        }
+
+        let db =
+            super::Database::<true, Config, Books>::open(&test_db_dir, vec![], Default::default())
+                .unwrap();
+        let copy_db =
+            super::Database::<true, Config, Books>::open(&copy_db_dir, vec![], Default::default())
+                .unwrap();
+
+        assert!(db.compare(&copy_db).unwrap().is_empty());
 
-    pub fn read_books(&self) -> Result<B, mapper::Error> {
-        Self::read_books_with_db(&self.db)
-    }
+        copy_db
+            .write_config(&Config {
+                case_sensitive: true,
+                ..config
+            })
+            .unwrap();
 
-    fn open_internal<P: AsRef<Path>>(
-        path: P,
-        mut cfs: Vec<ColumnFamilyDescriptor>,
-        options: Options,
-        optimistic_transactions: bool,
-    ) -> Result<Self, Error> {
-        let config_cf = ColumnFamilyDescriptor::new(CONFIG_CF_NAME, Options::default());
-        let books_cf = ColumnFamilyDescriptor::new(BOOKS_CF_NAME, Options::default());
+        let report = db.compare(&copy_db).unwrap();
 
-        cfs.push(config_cf);
-        cfs.push(books_cf);
+        assert!(report.books.is_empty());
+        assert!(report.config.only_in_self.is_empty());
+        assert!(report.config.only_in_other.is_empty());
+        assert_eq!(
+            report.config.different,
+            vec![b"case_sensitive".to_vec().into_boxed_slice()]
+        );
+    }
 
-        let db: Db = if !W {
-            DB::open_cf_descriptors_read_only(&options, path, cfs, false)?.into()
-        } else if optimistic_transactions {
-            OptimisticTransactionDB::open_cf_descriptors(&options, path, cfs)?.into()
-        } else {
-            let transaction_options = TransactionDBOptions::default();
+    #[test]
+    fn serialize_writes() {
+        // The large second field widens the window between the first write and the commit, so
+        // that unserialized writes reliably conflict.
+        #[derive(Default, serde_derive::Deserialize, serde_derive::Serialize)]
+        struct Payload {
+            id: u64,
+            data: Vec<u8>,
+        }
 
-            TransactionDB::open_cf_descriptors(&options, &transaction_options, path, cfs)?.into()
-        };
+        let conflicts = |serialize_writes: bool| {
+            let test_db_dir = tempfile::tempdir().unwrap();
 
-        let config = Self::read_config_with_db(&db)?;
-        let books = Self::read_books_with_db(&db)?;
+            let writeable_db = super::Database::create(
+                &test_db_dir,
+                vec![],
+                Default::default(),
+                true,
+                Payload::default(),
+                (),
+            )
+            .unwrap();
 
-        Ok(Self { db, config, books })
-    }
+            writeable_db.set_serialize_writes(serialize_writes);
 
-    fn read_config_with_db(db: &Db) -> Result<C, mapper::Error> {
-        C::deserialize(&Self::config_mapper(db))
-    }
+            // Start each round of writes together so that unserialized writes overlap.
+            let barrier = std::sync::Barrier::new(4);
 
-    fn read_books_with_db(db: &Db) -> Result<B, mapper::Error> {
-        B::deserialize(&Self::books_mapper(db))
-    }
-}
+            std::thread::scope(|scope| {
+                let handles = (0..4)
+                    .map(|id| {
+                        let writeable_db = &writeable_db;
+                        let barrier = &barrier;
 
-impl<C, B> Database<true, C, B> {
-    pub fn admin<P: AsRef<Path>>(
-        path: P,
-        mut cfs: Vec<ColumnFamilyDescriptor>,
-    ) -> Result<Admin, Error> {
-        let config_cf = ColumnFamilyDescriptor::new(CONFIG_CF_NAME, Options::default());
-        let books_cf = ColumnFamilyDescriptor::new(BOOKS_CF_NAME, Options::default());
+                        scope.spawn(move || {
+                            let payload = Payload {
+                                id,
+                                data: vec![0; 1 << 16],
+                            };
 
-        cfs.push(config_cf);
-        cfs.push(books_cf);
+                            (0..20)
+                                .filter(|_| {
+                                    barrier.wait();
+                                    writeable_db.write_config(&payload).is_err()
+                                })
+                                .count()
+                        })
+                    })
+                    .collect::<Vec<_>>();
 
-        let cf_names = cfs.iter().map(|cf| cf.name().to_string()).collect();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap())
+                    .sum::<usize>()
+            })
+        };
 
-        Ok(Admin {
-            underlying: DB::open_cf_descriptors(&Options::default(), path, cfs)?,
-            cf_names,
-        })
-    }
-}
+        let serialized_conflicts = conflicts(true);
+        let unserialized_conflicts = conflicts(false);
 
-impl<C, B> Database<false, C, B> {
-    pub fn underlying(&self) -> &DB {
-        // Safe because we know statically that the database is read-only.
-        self.db.read_only().unwrap()
+        assert_eq!(serialized_conflicts, 0);
+        assert!(unserialized_conflicts > 0);
     }
-}
-
-pub struct Admin {
-    underlying: DB,
-    cf_names: Vec<String>,
-}
 
-impl Admin {
-    pub fn flush(&self) -> Result<(), rocksdb::Error> {
-        for cf_name in &self.cf_names {
-            if let Some(cf) = self.underlying.cf_handle(cf_name) {
-                self.underlying.flush_cf(cf)?;
-            }
+    #[test]
+    fn update_config() {
+        #[derive(
+            Clone, Debug, Eq, PartialEq, serde_derive::Deserialize, serde_derive::Serialize,
+        )]
+        struct Counter {
+            count: u64,
         }
 
-        Ok(())
-    }
+        let test_db_dir = tempfile::tempdir().unwrap();
 
-    pub fn compact(&self) -> Result<(), rocksdb::Error> {
-        let mut options = rocksdb::CompactOptions::default();
-        options.set_change_level(true);
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            Counter { count: 0 },
+            (),
+        )
+        .unwrap();
 
-        for cf_name in &self.cf_names {
-            if let Some(cf) = self.underlying.cf_handle(cf_name) {
-                self.underlying
-                    .compact_range_cf_opt::<&[u8], &[u8]>(cf, None, None, &options);
+        std::thread::scope(|scope| {
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    for _ in 0..50 {
+                        // Retry increments whose commit conflicted with the other thread.
+                        while writeable_db
+                            .update_config(|counter| counter.count += 1)
+                            .is_err()
+                        {}
+                    }
+                });
             }
-        }
+        });
 
-        self.underlying.wait_for_compact(&Default::default())
+        assert_eq!(writeable_db.read_config().unwrap(), Counter { count: 100 });
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use quickcheck_arbitrary_derive::QuickCheck;
+    #[test]
+    fn update_config_conflicts() {
+        #[derive(
+            Clone, Debug, Default, Eq, PartialEq, serde_derive::Deserialize, serde_derive::Serialize,
+        )]
+        struct Settings {
+            name: Option<String>,
+            count: u64,
+        }
 
-    #[derive(
-        Clone,
-        Copy,
-        Debug,
-        Default,
-        Eq,
-        PartialEq,
-        QuickCheck,
-        serde_derive::Deserialize,
-        serde_derive::Serialize,
-    )]
-    pub enum Hashes {
-        #[default]
-        Both,
-        Md5Only,
-        Sha256Only,
-    }
+        let test_db_dir = tempfile::tempdir().unwrap();
 
-    #[derive(
-        Clone, Debug, Eq, PartialEq, QuickCheck, serde_derive::Deserialize, serde_derive::Serialize,
-    )]
-    struct Config {
-        hashes: Hashes,
-        case_sensitive: bool,
-    }
+        let writeable_db = super::Database::create(
+            &test_db_dir,
+            vec![],
+            Default::default(),
+            true,
+            Settings::default(),
+            (),
+        )
+        .unwrap();
 
-    #[derive(
-        Clone, Debug, Eq, PartialEq, QuickCheck, serde_derive::Deserialize, serde_derive::Serialize,
-    )]
-    struct Books {
-        last_scrape_ms: u64,
-        region: String,
+        let concurrent = Settings {
+            name: Some("foo".to_string()),
+            count: 0,
+        };
+
+        // A field that was not stored when the config was read is still checked for conflicts.
+        let result = writeable_db.update_config(|settings| {
+            writeable_db.write_config(&concurrent).unwrap();
+            settings.count += 1;
+        });
+
+        assert!(result.is_err());
+        assert_eq!(writeable_db.read_config().unwrap(), concurrent);
+
+        let without_transactions_dir = tempfile::tempdir().unwrap();
+        let without_transactions_db = crate::builder::DatabaseBuilder::new()
+            .path(without_transactions_dir.path())
+            .without_transactions()
+            .config(Settings::default())
+            .create()
+            .unwrap();
+
+        assert!(matches!(
+            without_transactions_db.update_config(|settings| settings.count += 1),
+            Err(super::Error::Unsupported)
+        ));
     }
 
-    #[quickcheck_macros::quickcheck]
-    fn round_trip_instantiate(config: Config, books: Books) -> bool {
+    #[test]
+    fn write_config_retry() {
         let test_db_dir = tempfile::tempdir().unwrap();
 
         let writeable_db = super::Database::create(
@@ -270,38 +4017,152 @@ mod tests {
             vec![],
             Default::default(),
             true,
-            config.clone(),
-            books.clone(),
+            Config::default(),
+            (),
         )
         .unwrap();
 
-        writeable_db.db.close();
+        let configs = [
+            Config {
+                hashes: Hashes::Md5Only,
+                case_sensitive: true,
+            },
+            Config {
+                hashes: Hashes::Sha256Only,
+                case_sensitive: false,
+            },
+        ];
+        let barrier = std::sync::Barrier::new(configs.len());
 
-        let read_only_db =
-            super::Database::<true, Config, Books>::open(test_db_dir, vec![], Default::default())
-                .unwrap();
+        // Concurrent unserialized writes conflict, but every write succeeds after retrying.
+        std::thread::scope(|scope| {
+            let handles = configs
+                .iter()
+                .map(|config| {
+                    let writeable_db = &writeable_db;
+                    let barrier = &barrier;
 
-        read_only_db.config == config && read_only_db.books == books
+                    scope.spawn(move || {
+                        (0..50).try_for_each(|_| {
+                            barrier.wait();
+                            writeable_db.write_config_retry(config, 100)
+                        })
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            for handle in handles {
+                handle.join().unwrap().unwrap();
+            }
+        });
+
+        assert!(configs.contains(&writeable_db.read_config().unwrap()));
     }
 
-    #[quickcheck_macros::quickcheck]
-    fn round_trip_instantiate_with_unit(config: (), books: ()) -> bool {
+    #[test]
+    fn custom_env() {
         let test_db_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            hashes: Hashes::Md5Only,
+            case_sensitive: true,
+        };
+        let options = || {
+            let mut env = rocksdb::Env::new().unwrap();
+            env.set_background_threads(2);
+            env.lower_thread_pool_io_priority();
+
+            let mut options = rocksdb::Options::default();
+            options.set_env(&env);
+            options.set_ratelimiter(1024 * 1024, 100_000, 10);
+            options
+        };
 
         let writeable_db =
-            super::Database::create(&test_db_dir, vec![], Default::default(), true, (), ())
+            super::Database::create(&test_db_dir, vec![], options(), true, config.clone(), ())
                 .unwrap();
 
+        writeable_db
+            .insert_books_entries((0..100).map(|i| (format!("key-{i}"), i as u64)))
+            .unwrap();
+        writeable_db.flush().unwrap();
         writeable_db.db.close();
 
-        let read_only_db =
-            super::Database::<true, (), ()>::open(test_db_dir, vec![], Default::default()).unwrap();
+        let writeable_db =
+            super::Database::<true, Config, ()>::open(&test_db_dir, vec![], options()).unwrap();
 
-        read_only_db.config == config && read_only_db.books == books
+        assert_eq!(writeable_db.read_config().unwrap(), config);
+        assert_eq!(writeable_db.read_books_stream::<u64>().count(), 100);
     }
 
-    #[quickcheck_macros::quickcheck]
-    fn round_trip_instantiate_with_pessimistic_transactions(config: Config, books: Books) -> bool {
+    #[test]
+    fn open_or_create() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+        let books = Books {
+            last_scrape_ms: 1,
+            region: "us".to_string(),
+        };
+        let new_config = Config {
+            hashes: Hashes::Sha256Only,
+            case_sensitive: true,
+        };
+
+        let writeable_db = super::Database::open_or_create(
+            test_db_dir.path().join("db"),
+            vec![],
+            Default::default(),
+            super::OpenMode::WithoutTransactions,
+            Config::default(),
+            books.clone(),
+        )
+        .unwrap();
+
+        assert_eq!(writeable_db.read_config().unwrap(), Config::default());
+        assert_eq!(writeable_db.read_books().unwrap(), books);
+
+        writeable_db.write_config(&new_config).unwrap();
+        writeable_db.db.close();
+
+        let writeable_db = super::Database::open_or_create(
+            test_db_dir.path().join("db"),
+            vec![],
+            Default::default(),
+            super::OpenMode::WithoutTransactions,
+            Config::default(),
+            Books {
+                last_scrape_ms: 2,
+                region: "us".to_string(),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(writeable_db.config, new_config);
+        assert_eq!(writeable_db.books, books);
+        assert_eq!(writeable_db.read_config().unwrap(), new_config);
+
+        // A database that fails to open is not recreated.
+        let corrupt_path = test_db_dir.path().join("corrupt");
+        std::fs::create_dir(&corrupt_path).unwrap();
+        std::fs::write(corrupt_path.join("CURRENT"), "garbage\n").unwrap();
+
+        assert!(super::Database::open_or_create(
+            &corrupt_path,
+            vec![],
+            Default::default(),
+            super::OpenMode::WithoutTransactions,
+            Config::default(),
+            books,
+        )
+        .is_err());
+        assert_eq!(
+            std::fs::read_to_string(corrupt_path.join("CURRENT")).unwrap(),
+            "garbage\n"
+        );
+    }
+
+    #[test]
+    fn overlapping_mapper_writes() {
+        use serde::ser::{SerializeStruct, Serializer};
+
         let test_db_dir = tempfile::tempdir().unwrap();
 
         let writeable_db = super::Database::create(
@@ -309,27 +4170,52 @@ mod tests {
             vec![],
             Default::default(),
             false,
-            config.clone(),
-            books.clone(),
+            Config {
+                hashes: Hashes::Both,
+                case_sensitive: false,
+            },
+            (),
         )
         .unwrap();
 
-        writeable_db.db.close();
+        let new_config = Config {
+            hashes: Hashes::Md5Only,
+            case_sensitive: true,
+        };
 
-        let read_only_db =
-            super::Database::<true, Config, Books>::open(test_db_dir, vec![], Default::default())
+        let mapper = writeable_db
+            .table_mapper(super::CONFIG_CF_NAME, bincode::config::standard())
+            .unwrap();
+        let mut outstanding = mapper.serialize_struct("Config", 2).unwrap();
+        outstanding
+            .serialize_field("case_sensitive", &true)
+            .unwrap();
+
+        // With pessimistic transactions this would otherwise block on the row lock.
+        assert!(matches!(
+            writeable_db.clone().write_config(&new_config),
+            Err(super::mapper::Error::InvalidTransaction)
+        ));
+
+        // Writes to other tables, or from other threads, are not rejected.
+        writeable_db.write_books(&()).unwrap();
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| writeable_db.clone().write_books(&()))
+                .join()
+                .unwrap()
                 .unwrap();
+        });
 
-        read_only_db.config == config && read_only_db.books == books
+        outstanding.end().unwrap();
+
+        writeable_db.write_config(&new_config).unwrap();
+
+        assert_eq!(writeable_db.read_config().unwrap(), new_config);
     }
 
     #[quickcheck_macros::quickcheck]
-    fn round_trip_write(
-        config: Config,
-        books: Books,
-        new_config: Config,
-        new_books: Books,
-    ) -> bool {
+    fn compare_and_swap_config(config: Config, new_config: Config) -> bool {
         let test_db_dir = tempfile::tempdir().unwrap();
 
         let writeable_db = super::Database::create(
@@ -338,60 +4224,124 @@ mod tests {
             Default::default(),
             true,
             config.clone(),
-            books.clone(),
+            (),
         )
         .unwrap();
 
-        writeable_db.db.close();
+        let other_config = Config {
+            hashes: config.hashes,
+            case_sensitive: !config.case_sensitive,
+        };
 
-        let writeable_db =
-            super::Database::<true, Config, Books>::open(&test_db_dir, vec![], Default::default())
-                .unwrap();
+        let not_swapped = !writeable_db
+            .compare_and_swap_config(&other_config, &new_config)
+            .unwrap();
+        let unchanged = writeable_db.read_config().unwrap() == config;
+        let swapped = writeable_db
+            .compare_and_swap_config(&config, &new_config)
+            .unwrap();
 
-        assert!(writeable_db.config == config && writeable_db.books == books);
+        not_swapped && unchanged && swapped && writeable_db.read_config().unwrap() == new_config
+    }
 
-        writeable_db.write_config(&new_config).unwrap();
-        writeable_db.write_books(&new_books).unwrap();
+    #[test]
+    fn compare_and_swap_config_without_transactions() {
+        let test_db_dir = tempfile::tempdir().unwrap();
 
-        writeable_db.read_config().unwrap() == new_config
-            && writeable_db.read_books().unwrap() == new_books
+        let writeable_db = crate::builder::DatabaseBuilder::new()
+            .path(test_db_dir.path())
+            .without_transactions()
+            .config(Config::default())
+            .create()
+            .unwrap();
+
+        assert!(matches!(
+            writeable_db.compare_and_swap_config(&Config::default(), &Config::default()),
+            Err(super::Error::Unsupported)
+        ));
     }
 
-    #[quickcheck_macros::quickcheck]
-    fn round_trip_write_with_pessimistic_transactions(
-        config: Config,
-        books: Books,
-        new_config: Config,
-        new_books: Books,
-    ) -> bool {
+    #[test]
+    fn read_config_lenient() {
         let test_db_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            hashes: Hashes::Sha256Only,
+            case_sensitive: true,
+        };
 
         let writeable_db = super::Database::create(
             &test_db_dir,
             vec![],
             Default::default(),
-            false,
+            true,
             config.clone(),
-            books.clone(),
+            (),
         )
         .unwrap();
 
-        writeable_db.db.close();
+        assert_eq!(writeable_db.read_config_lenient().unwrap(), config);
+
+        // An out-of-range variant index for the enum field.
+        writeable_db.write_config_field("hashes", &200u32).unwrap();
+
+        assert!(matches!(
+            writeable_db.read_config(),
+            Err(super::mapper::Error::FieldDecoding { field, .. }) if field == "hashes"
+        ));
+
+        let mut fallbacks = vec![];
+        let lenient_config = writeable_db
+            .read_config_lenient_with(|field, _| fallbacks.push(field.to_string()))
+            .unwrap();
+
+        assert_eq!(
+            lenient_config,
+            Config {
+                hashes: Hashes::default(),
+                case_sensitive: true,
+            }
+        );
+        assert_eq!(fallbacks, vec!["hashes"]);
+
+        // Every field that cannot be decoded falls back in the same read.
+        writeable_db
+            .write_config_field("case_sensitive", &2u8)
+            .unwrap();
+
+        let mut fallbacks = vec![];
+        let lenient_config = writeable_db
+            .read_config_lenient_with(|field, _| fallbacks.push(field.to_string()))
+            .unwrap();
+
+        assert_eq!(lenient_config, Config::default());
+        assert_eq!(fallbacks, vec!["hashes", "case_sensitive"]);
+    }
+
+    #[test]
+    fn books_entries() {
+        let test_db_dir = tempfile::tempdir().unwrap();
 
         let writeable_db =
-            super::Database::<true, Config, Books>::open_with_pessimistic_transactions(
-                &test_db_dir,
-                vec![],
-                Default::default(),
-            )
+            super::Database::create(&test_db_dir, vec![], Default::default(), true, (), ())
+                .unwrap();
+
+        writeable_db
+            .insert_books_entries((0..10).map(|i| (format!("key-{i}"), i as u64)))
+            .unwrap();
+        writeable_db
+            .remove_books_entries((0..10).step_by(3).map(|i| format!("key-{i}")))
             .unwrap();
 
-        assert!(writeable_db.config == config && writeable_db.books == books);
+        let books = writeable_db
+            .read_books_stream::<u64>()
+            .collect::<Result<std::collections::BTreeMap<_, _>, _>>()
+            .unwrap();
 
-        writeable_db.write_config(&new_config).unwrap();
-        writeable_db.write_books(&new_books).unwrap();
+        let expected = [1, 2, 4, 5, 7, 8]
+            .into_iter()
+            .map(|i| (format!("key-{i}"), i))
+            .collect::<std::collections::BTreeMap<_, _>>();
 
-        writeable_db.read_config().unwrap() == new_config
-            && writeable_db.read_books().unwrap() == new_books
+        assert_eq!(books, expected);
     }
 }
@@ -1,9 +1,12 @@
-use crate::wrapper::Transaction;
+use crate::wrapper::{KeyValuePair, MapperWriteSlot, Transaction};
 
 use super::wrapper::Db;
 use bincode::serde::OwnedSerdeDecoder;
-use rocksdb::ColumnFamily;
-use std::io::{BufReader, Cursor};
+use rocksdb::{ColumnFamily, IteratorMode};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{BufReader, Cursor, Read};
+use std::marker::PhantomData;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -14,9 +17,27 @@ pub enum Error {
     #[error("Encoding error")]
     Encoding(#[from] bincode::error::EncodeError),
     #[error("Decoding error")]
-    Decoding(bincode::error::DecodeError),
+    Decoding(#[source] bincode::error::DecodeError),
+    #[error("Decoding error for field {field}")]
+    FieldDecoding {
+        field: String,
+        source: bincode::error::DecodeError,
+    },
+    #[error("Missing column family {0}")]
+    MissingColumnFamily(String),
+    #[error("{count} trailing bytes after decoding field {field}")]
+    TrailingBytes { field: String, count: usize },
+    #[error("Map key {0} is reserved")]
+    ReservedKey(String),
+    #[error("Missing stored field {0}")]
+    MissingStoredField(String),
+    #[error("Entry decoding error")]
+    EntryDecoding {
+        key: Box<[u8]>,
+        source: bincode::error::DecodeError,
+    },
     #[error("Serde error")]
-    Serde(serde::de::value::Error),
+    Serde(#[source] serde::de::value::Error),
     #[error("RocksDb error")]
     Db(#[from] rocksdb::Error),
 }
@@ -49,7 +70,7 @@ impl serde::de::Error for Error {
     }
 
     fn missing_field(field: &'static str) -> Self {
-        Self::Serde(serde::de::value::Error::missing_field(field))
+        Self::MissingStoredField(field.to_string())
     }
 
     fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
@@ -61,12 +82,31 @@ impl serde::de::Error for Error {
     }
 }
 
+/// Reserved key used to store the variant name when the mapped type is an enum.
+pub(super) const VARIANT_KEY: &str = "_variant";
+
+/// Reserved key marking a stored `None` when the mapped type is an option.
+pub(super) const NONE_KEY: &str = "_none";
+
 /// Maps a serializable struct onto a column family.
+///
+/// Only one mapper per thread may have uncommitted writes to a given column family in its own
+/// transaction on a database (including its clones). Writing through a second mapper on the same
+/// thread while another has writes outstanding fails with [`Error::InvalidTransaction`] instead of
+/// blocking on its own row locks. This does not apply to mappers that write into an existing
+/// transaction, and writers on other threads are not affected.
 pub struct TableMapper<'a, const W: bool, C> {
     db: &'a Db,
     tx: Option<Transaction<'a>>,
+    external_tx: Option<&'a Transaction<'a>>,
     cf: &'a ColumnFamily,
     bincode_config: C,
+    write_slot: Option<MapperWriteSlot>,
+    written_bytes: usize,
+    captured: Option<&'a mut HashMap<String, Vec<u8>>>,
+    map_key: Option<String>,
+    defaults: HashMap<String, Vec<u8>>,
+    fallbacks: RefCell<Vec<(String, Error)>>,
 }
 
 impl<'a, const W: bool, C> TableMapper<'a, W, C> {
@@ -79,8 +119,190 @@ impl<'a, const W: bool, C> TableMapper<'a, W, C> {
             } else {
                 None
             },
+            external_tx: None,
+            cf,
+            bincode_config,
+            write_slot: None,
+            written_bytes: 0,
+            captured: None,
+            map_key: None,
+            defaults: HashMap::new(),
+            fallbacks: RefCell::default(),
+        }
+    }
+}
+
+impl<'a, const W: bool, C> TableMapper<'a, W, C> {
+    /// Decode the given bytes for fields whose stored values cannot be decoded.
+    ///
+    /// This only applies to fields read with `next_value` (as derived implementations do), and
+    /// each substituted field is recorded with its decoding error.
+    pub(super) fn with_defaults(mut self, defaults: HashMap<String, Vec<u8>>) -> Self {
+        self.defaults = defaults;
+        self
+    }
+
+    /// The fields whose defaults were used, with the errors decoding their stored values.
+    pub(super) fn take_fallbacks(&self) -> Vec<(String, Error)> {
+        self.fallbacks.take()
+    }
+
+    // Reads inside an existing transaction register their keys for conflict checking, so that
+    // callers can read, modify, and write the table atomically.
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        let value = match self.external_tx {
+            Some(tx) => tx.get_for_update(self.cf, key)?.map(|value| value.to_vec()),
+            None => self.db.get(self.cf, key)?.map(|value| value.to_vec()),
+        };
+
+        Ok(value)
+    }
+
+    fn multi_get(&self, keys: &[&str]) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        match self.external_tx {
+            Some(_) => keys.iter().map(|key| self.get(key)).collect(),
+            None => Ok(self.db.multi_get(self.cf, keys)?),
+        }
+    }
+
+    /// The stored entries in key order, apart from reserved keys.
+    fn entries(&self) -> Result<Vec<KeyValuePair>, Error> {
+        match self.external_tx {
+            Some(tx) => {
+                let mut entries = vec![];
+
+                for result in tx.iterator(self.cf, IteratorMode::Start) {
+                    let (key, value) = result?;
+
+                    if !crate::is_reserved_key(&key) {
+                        tx.get_for_update(self.cf, &key)?;
+                        entries.push((key, value));
+                    }
+                }
+
+                Ok(entries)
+            }
+            None => Ok(crate::field_entries(self.db, self.cf).collect::<Result<_, _>>()?),
+        }
+    }
+}
+
+impl<'a, C> TableMapper<'a, true, C> {
+    /// Create a mapper that encodes fields into the given map instead of writing them.
+    pub(super) fn capturing(
+        db: &'a Db,
+        cf: &'a ColumnFamily,
+        bincode_config: C,
+        captured: &'a mut HashMap<String, Vec<u8>>,
+    ) -> Self {
+        Self {
+            db,
+            tx: None,
+            external_tx: None,
+            cf,
+            bincode_config,
+            write_slot: None,
+            written_bytes: 0,
+            captured: Some(captured),
+            map_key: None,
+            defaults: HashMap::new(),
+            fallbacks: RefCell::default(),
+        }
+    }
+
+    /// Create a mapper that writes into an existing transaction, which the caller must commit.
+    pub(super) fn with_transaction(
+        db: &'a Db,
+        tx: &'a Transaction<'a>,
+        cf: &'a ColumnFamily,
+        bincode_config: C,
+    ) -> Self {
+        Self {
+            db,
+            tx: None,
+            external_tx: Some(tx),
             cf,
             bincode_config,
+            write_slot: None,
+            written_bytes: 0,
+            captured: None,
+            map_key: None,
+            defaults: HashMap::new(),
+            fallbacks: RefCell::default(),
+        }
+    }
+}
+
+impl<'a, C: bincode::config::Config> TableMapper<'a, true, C> {
+    fn writer(&mut self) -> Result<&Transaction<'a>, Error> {
+        if self.tx.is_some() && self.write_slot.is_none() {
+            self.write_slot = Some(
+                self.db
+                    .begin_mapper_write(self.cf)
+                    .ok_or(Error::InvalidTransaction)?,
+            );
+        }
+
+        self.tx
+            .as_ref()
+            .or(self.external_tx)
+            .ok_or(Error::InvalidTransaction)
+    }
+
+    fn put<T: ?Sized + serde::Serialize>(&mut self, key: &str, value: &T) -> Result<(), Error> {
+        let value_bytes = bincode::serde::encode_to_vec(value, self.bincode_config)?;
+
+        if let Some(captured) = self.captured.as_mut() {
+            captured.insert(key.to_string(), value_bytes);
+
+            return Ok(());
+        }
+
+        let bytes = value_bytes.len();
+        let cf = self.cf;
+
+        self.writer()?.put(cf, key.as_bytes(), value_bytes)?;
+        self.written_bytes += bytes;
+
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        if let Some(captured) = self.captured.as_mut() {
+            captured.remove(key);
+
+            return Ok(());
+        }
+
+        let cf = self.cf;
+
+        Ok(self.writer()?.delete(cf, key.as_bytes())?)
+    }
+
+    // The mapper write slot is released when the mapper is dropped after committing.
+    fn commit(mut self) -> Result<(), Error> {
+        // Writes into an external transaction are committed by the caller.
+        if self.external_tx.is_some() || self.captured.is_some() {
+            return Ok(());
+        }
+
+        self.tx.take().ok_or(Error::InvalidTransaction)?.commit()?;
+
+        if self.write_slot.is_some() {
+            self.db.record_write(self.cf, self.written_bytes);
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, const W: bool, C> Drop for TableMapper<'a, W, C> {
+    fn drop(&mut self) {
+        // Roll back any uncommitted writes before allowing another mapper to write.
+        self.tx.take();
+
+        if let Some(slot) = self.write_slot.take() {
+            self.db.end_mapper_write(slot);
         }
     }
 }
@@ -94,22 +316,11 @@ impl<'a, C: bincode::config::Config> serde::ser::SerializeStruct for TableMapper
         key: &'static str,
         value: &T,
     ) -> Result<(), Self::Error> {
-        let value_bytes = bincode::serde::encode_to_vec(value, self.bincode_config)?;
-
-        self.tx
-            .as_ref()
-            .ok_or(Error::InvalidTransaction)
-            .and_then(|tx| {
-                tx.put(self.cf, key.as_bytes(), value_bytes)
-                    .map_err(Error::from)
-            })
+        self.put(key, value)
     }
 
-    fn end(mut self) -> Result<Self::Ok, Self::Error> {
-        self.tx
-            .take()
-            .ok_or(Error::InvalidTransaction)
-            .and_then(|tx| tx.commit().map_err(Error::from))
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.commit()
     }
 }
 
@@ -134,7 +345,7 @@ impl<'a, C: bincode::config::Config> serde::ser::Serializer for TableMapper<'a,
     }
 
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+        self.commit()
     }
 
     fn serialize_bool(self, _v: bool) -> Result<Self::Ok, Self::Error> {
@@ -177,30 +388,46 @@ impl<'a, C: bincode::config::Config> serde::ser::Serializer for TableMapper<'a,
         Err(Error::Unsupported)
     }
 
-    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        Err(Error::Unsupported)
+    fn serialize_map(mut self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        let (db, cf) = (self.db, self.cf);
+
+        if self.captured.is_some() {
+            return Ok(self);
+        }
+
+        // The map replaces the contents of the table, apart from reserved keys.
+        for result in crate::field_entries(db, cf) {
+            let (key, _) = result?;
+
+            self.writer()?.delete(cf, &key)?;
+        }
+
+        Ok(self)
     }
 
     fn serialize_newtype_struct<T: ?Sized + serde::Serialize>(
         self,
         _name: &'static str,
-        _value: &T,
+        value: &T,
     ) -> Result<Self::Ok, Self::Error> {
-        Err(Error::Unsupported)
+        value.serialize(self)
     }
 
     fn serialize_newtype_variant<T: ?Sized + serde::Serialize>(
-        self,
+        mut self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
-        _value: &T,
+        variant: &'static str,
+        value: &T,
     ) -> Result<Self::Ok, Self::Error> {
-        Err(Error::Unsupported)
+        self.put(VARIANT_KEY, variant)?;
+        value.serialize(self)
     }
 
-    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
-        Err(Error::Unsupported)
+    fn serialize_none(mut self) -> Result<Self::Ok, Self::Error> {
+        // Stored as a marker key, leaving any previously stored fields in place.
+        self.put(NONE_KEY, &())?;
+        self.commit()
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
@@ -208,10 +435,11 @@ impl<'a, C: bincode::config::Config> serde::ser::Serializer for TableMapper<'a,
     }
 
     fn serialize_some<T: ?Sized + serde::Serialize>(
-        self,
-        _value: &T,
+        mut self,
+        value: &T,
     ) -> Result<Self::Ok, Self::Error> {
-        Err(Error::Unsupported)
+        self.delete(NONE_KEY)?;
+        value.serialize(self)
     }
 
     fn serialize_str(self, _v: &str) -> Result<Self::Ok, Self::Error> {
@@ -219,13 +447,14 @@ impl<'a, C: bincode::config::Config> serde::ser::Serializer for TableMapper<'a,
     }
 
     fn serialize_struct_variant(
-        self,
+        mut self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        Err(Error::Unsupported)
+        self.put(VARIANT_KEY, variant)?;
+        Ok(self)
     }
 
     fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
@@ -275,40 +504,46 @@ impl<'a, C: bincode::config::Config> serde::ser::Serializer for TableMapper<'a,
     }
 
     fn serialize_unit_variant(
-        self,
+        mut self,
         _name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        Err(Error::Unsupported)
+        self.put(VARIANT_KEY, variant)?;
+        self.commit()
     }
 }
 
-impl<'a, C> serde::ser::SerializeMap for TableMapper<'a, true, C> {
+impl<'a, C: bincode::config::Config> serde::ser::SerializeMap for TableMapper<'a, true, C> {
     type Ok = ();
     type Error = Error;
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Err(Error::Unsupported)
+        self.commit()
     }
 
-    fn serialize_entry<K: ?Sized + serde::Serialize, V: ?Sized + serde::Serialize>(
-        &mut self,
-        _key: &K,
-        _value: &V,
-    ) -> Result<(), Self::Error> {
-        Err(Error::Unsupported)
-    }
+    fn serialize_key<T: ?Sized + serde::Serialize>(&mut self, key: &T) -> Result<(), Self::Error> {
+        let key = key.serialize(MapKeySerializer)?;
 
-    fn serialize_key<T: ?Sized + serde::Serialize>(&mut self, _key: &T) -> Result<(), Self::Error> {
-        Err(Error::Unsupported)
+        // Writing a reserved key would overwrite metadata such as the endianness marker.
+        if crate::RESERVED_KEYS.contains(&key.as_str()) {
+            return Err(Error::ReservedKey(key));
+        }
+
+        self.map_key = Some(key);
+
+        Ok(())
     }
 
     fn serialize_value<T: ?Sized + serde::Serialize>(
         &mut self,
-        _value: &T,
+        value: &T,
     ) -> Result<(), Self::Error> {
-        Err(Error::Unsupported)
+        let key = self.map_key.take().ok_or_else(|| {
+            <Error as serde::ser::Error>::custom("serialize_value called before serialize_key")
+        })?;
+
+        self.put(&key, value)
     }
 }
 
@@ -328,24 +563,22 @@ impl<'a, C> serde::ser::SerializeSeq for TableMapper<'a, true, C> {
     }
 }
 
-impl<'a, C> serde::ser::SerializeStructVariant for TableMapper<'a, true, C> {
+impl<'a, C: bincode::config::Config> serde::ser::SerializeStructVariant
+    for TableMapper<'a, true, C>
+{
     type Ok = ();
     type Error = Error;
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Err(Error::Unsupported)
+        self.commit()
     }
 
     fn serialize_field<T: ?Sized + serde::Serialize>(
         &mut self,
-        _key: &'static str,
-        _value: &T,
+        key: &'static str,
+        value: &T,
     ) -> Result<(), Self::Error> {
-        Err(Error::Unsupported)
-    }
-
-    fn skip_field(&mut self, _key: &'static str) -> Result<(), Self::Error> {
-        Err(Error::Unsupported)
+        self.put(key, value)
     }
 }
 
@@ -397,160 +630,336 @@ impl<'a, C> serde::ser::SerializeTupleVariant for TableMapper<'a, true, C> {
     }
 }
 
-impl<'a, 'de: 'a, const W: bool, C: bincode::config::Config> serde::de::Deserializer<'de>
-    for &TableMapper<'a, W, C>
-{
+/// Serializer for map keys, which must be strings.
+struct MapKeySerializer;
+
+impl serde::ser::Serializer for MapKeySerializer {
+    type Ok = String;
     type Error = Error;
 
-    fn deserialize_struct<V: serde::de::Visitor<'de>>(
-        self,
-        _name: &'static str,
-        fields: &'static [&'static str],
-        visitor: V,
-    ) -> Result<V::Value, Self::Error> {
-        visitor.visit_map(TableMapperAccess {
-            table: self,
-            fields,
-        })
+    type SerializeSeq = serde::ser::Impossible<String, Error>;
+    type SerializeTuple = serde::ser::Impossible<String, Error>;
+    type SerializeTupleStruct = serde::ser::Impossible<String, Error>;
+    type SerializeTupleVariant = serde::ser::Impossible<String, Error>;
+    type SerializeMap = serde::ser::Impossible<String, Error>;
+    type SerializeStruct = serde::ser::Impossible<String, Error>;
+    type SerializeStructVariant = serde::ser::Impossible<String, Error>;
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(v.to_string())
     }
 
-    fn is_human_readable(&self) -> bool {
-        false
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        Ok(v.to_string())
     }
 
-    fn deserialize_any<V: serde::de::Visitor<'de>>(
+    fn serialize_newtype_struct<T: ?Sized + serde::Serialize>(
         self,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
-        Err(Error::Unsupported)
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
     }
 
-    fn deserialize_bool<V: serde::de::Visitor<'de>>(
-        self,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
+    fn serialize_bool(self, _v: bool) -> Result<Self::Ok, Self::Error> {
         Err(Error::Unsupported)
     }
 
-    fn deserialize_byte_buf<V: serde::de::Visitor<'de>>(
-        self,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
+    fn serialize_i8(self, _v: i8) -> Result<Self::Ok, Self::Error> {
         Err(Error::Unsupported)
     }
 
-    fn deserialize_bytes<V: serde::de::Visitor<'de>>(
-        self,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
+    fn serialize_i16(self, _v: i16) -> Result<Self::Ok, Self::Error> {
         Err(Error::Unsupported)
     }
 
-    fn deserialize_char<V: serde::de::Visitor<'de>>(
-        self,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
+    fn serialize_i32(self, _v: i32) -> Result<Self::Ok, Self::Error> {
         Err(Error::Unsupported)
     }
 
-    fn deserialize_enum<V: serde::de::Visitor<'de>>(
-        self,
-        _name: &'static str,
-        _variants: &'static [&'static str],
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
+    fn serialize_i64(self, _v: i64) -> Result<Self::Ok, Self::Error> {
         Err(Error::Unsupported)
     }
 
-    fn deserialize_f32<V: serde::de::Visitor<'de>>(
-        self,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
+    fn serialize_u8(self, _v: u8) -> Result<Self::Ok, Self::Error> {
         Err(Error::Unsupported)
     }
 
-    fn deserialize_f64<V: serde::de::Visitor<'de>>(
-        self,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
+    fn serialize_u16(self, _v: u16) -> Result<Self::Ok, Self::Error> {
         Err(Error::Unsupported)
     }
 
-    fn deserialize_i16<V: serde::de::Visitor<'de>>(
-        self,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
+    fn serialize_u32(self, _v: u32) -> Result<Self::Ok, Self::Error> {
         Err(Error::Unsupported)
     }
 
-    fn deserialize_i32<V: serde::de::Visitor<'de>>(
-        self,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
+    fn serialize_u64(self, _v: u64) -> Result<Self::Ok, Self::Error> {
         Err(Error::Unsupported)
     }
 
-    fn deserialize_i64<V: serde::de::Visitor<'de>>(
-        self,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
+    fn serialize_f32(self, _v: f32) -> Result<Self::Ok, Self::Error> {
         Err(Error::Unsupported)
     }
 
-    fn deserialize_i8<V: serde::de::Visitor<'de>>(
-        self,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
+    fn serialize_f64(self, _v: f64) -> Result<Self::Ok, Self::Error> {
         Err(Error::Unsupported)
     }
 
-    fn deserialize_identifier<V: serde::de::Visitor<'de>>(
-        self,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
         Err(Error::Unsupported)
     }
 
-    fn deserialize_ignored_any<V: serde::de::Visitor<'de>>(
-        self,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
         Err(Error::Unsupported)
     }
 
-    fn deserialize_newtype_struct<V: serde::de::Visitor<'de>>(
+    fn serialize_some<T: ?Sized + serde::Serialize>(
         self,
-        _name: &'static str,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
         Err(Error::Unsupported)
     }
 
-    fn deserialize_map<V: serde::de::Visitor<'de>>(
-        self,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
         Err(Error::Unsupported)
     }
 
-    fn deserialize_option<V: serde::de::Visitor<'de>>(
-        self,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
         Err(Error::Unsupported)
     }
 
-    fn deserialize_seq<V: serde::de::Visitor<'de>>(
+    fn serialize_unit_variant(
         self,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
         Err(Error::Unsupported)
     }
 
-    fn deserialize_str<V: serde::de::Visitor<'de>>(
+    fn serialize_newtype_variant<T: ?Sized + serde::Serialize>(
         self,
-        _visitor: V,
-    ) -> Result<V::Value, Self::Error> {
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(Error::Unsupported)
+    }
+}
+
+impl<'a, 'de: 'a, const W: bool, C: bincode::config::Config> serde::de::Deserializer<'de>
+    for &TableMapper<'a, W, C>
+{
+    type Error = Error;
+
+    fn deserialize_struct<V: serde::de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(TableMapperAccess::new(self, fields)?)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn deserialize_any<V: serde::de::Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn deserialize_bool<V: serde::de::Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn deserialize_byte_buf<V: serde::de::Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn deserialize_bytes<V: serde::de::Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn deserialize_char<V: serde::de::Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn deserialize_enum<V: serde::de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let bytes = self
+            .get(VARIANT_KEY)?
+            .ok_or_else(|| <Error as serde::de::Error>::missing_field(VARIANT_KEY))?;
+
+        let variant = decode_field(PhantomData, &bytes, self.bincode_config, VARIANT_KEY)?;
+
+        visitor.visit_enum(TableMapperEnumAccess {
+            table: self,
+            variant,
+        })
+    }
+
+    fn deserialize_f32<V: serde::de::Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn deserialize_f64<V: serde::de::Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn deserialize_i16<V: serde::de::Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn deserialize_i32<V: serde::de::Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn deserialize_i64<V: serde::de::Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn deserialize_i8<V: serde::de::Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn deserialize_identifier<V: serde::de::Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn deserialize_ignored_any<V: serde::de::Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn deserialize_newtype_struct<V: serde::de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_map<V: serde::de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(TableMapperEntries::new(self)?)
+    }
+
+    fn deserialize_option<V: serde::de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if self.get(NONE_KEY)?.is_some() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_seq<V: serde::de::Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn deserialize_str<V: serde::de::Visitor<'de>>(
+        self,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
         Err(Error::Unsupported)
     }
 
@@ -625,6 +1034,31 @@ impl<'a, 'de: 'a, const W: bool, C: bincode::config::Config> serde::de::Deserial
 struct TableMapperAccess<'a, const W: bool, C> {
     table: &'a TableMapper<'a, W, C>,
     fields: &'static [&'static str],
+    values: std::vec::IntoIter<Option<Vec<u8>>>,
+}
+
+impl<'a, const W: bool, C> TableMapperAccess<'a, W, C> {
+    fn next_field(&mut self) -> (&'static str, Vec<u8>) {
+        let field_name = self.fields[0];
+        self.fields = &self.fields[1..];
+
+        // Safe because we fetched exactly one value for each field, and skip missing values.
+        (field_name, self.values.next().flatten().unwrap())
+    }
+
+    /// Prefetch the values of all fields with a single multi-get.
+    fn new(
+        table: &'a TableMapper<'a, W, C>,
+        fields: &'static [&'static str],
+    ) -> Result<Self, Error> {
+        let values = table.multi_get(fields)?;
+
+        Ok(Self {
+            table,
+            fields,
+            values: values.into_iter(),
+        })
+    }
 }
 
 impl<'a, 'de: 'a, const W: bool, C: bincode::config::Config> serde::de::MapAccess<'de>
@@ -636,6 +1070,13 @@ impl<'a, 'de: 'a, const W: bool, C: bincode::config::Config> serde::de::MapAcces
         &mut self,
         seed: K,
     ) -> Result<Option<K::Value>, Self::Error> {
+        // Fields without stored values are skipped, so that Serde can supply defaults (or `None`
+        // for `Option` fields), and report any other missing fields.
+        while !self.fields.is_empty() && self.values.as_slice()[0].is_none() {
+            self.fields = &self.fields[1..];
+            self.values.next();
+        }
+
         if self.fields.is_empty() {
             Ok(None)
         } else {
@@ -649,35 +1090,185 @@ impl<'a, 'de: 'a, const W: bool, C: bincode::config::Config> serde::de::MapAcces
         &mut self,
         seed: V,
     ) -> Result<V::Value, Self::Error> {
-        // In the case that the field is not found, we return the Bincode representation for `None`.
-        const BINCODE_NONE_BYTES: [u8; 1] = [0];
+        let (field_name, bytes) = self.next_field();
+
+        decode_field(seed, &bytes, self.table.bincode_config, field_name)
+    }
+
+    // Overridden so that a field that cannot be decoded can be decoded again from its default,
+    // since unlike an arbitrary seed, a `PhantomData` seed can be recreated.
+    fn next_value<V: serde::de::Deserialize<'de>>(&mut self) -> Result<V, Self::Error> {
+        let (field_name, bytes) = self.next_field();
+        let bincode_config = self.table.bincode_config;
+
+        match decode_field(PhantomData, &bytes, bincode_config, field_name) {
+            Err(error @ (Error::FieldDecoding { .. } | Error::TrailingBytes { .. })) => {
+                match self.table.defaults.get(field_name) {
+                    Some(default) => {
+                        self.table
+                            .fallbacks
+                            .borrow_mut()
+                            .push((field_name.to_string(), error));
+
+                        decode_field(PhantomData, default, bincode_config, field_name)
+                    }
+                    None => Err(error),
+                }
+            }
+            result => result,
+        }
+    }
+}
 
-        let field_name = self.fields[0].as_bytes();
-        self.fields = &self.fields[1..];
+/// Decode a stored field value, failing if any bytes are left over.
+pub(crate) fn decode_field<'de, V: serde::de::DeserializeSeed<'de>, C: bincode::config::Config>(
+    seed: V,
+    bytes: &[u8],
+    bincode_config: C,
+    field_name: &str,
+) -> Result<V::Value, Error> {
+    let mut reader = BufReader::new(Cursor::new(bytes));
+    let mut deserializer = OwnedSerdeDecoder::from_reader(&mut reader, bincode_config);
+
+    let value = seed
+        .deserialize(deserializer.as_deserializer())
+        .map_err(|source| Error::FieldDecoding {
+            field: field_name.to_string(),
+            source,
+        })?;
+
+    // Safe because reading from an in-memory buffer cannot fail.
+    let count = reader.read_to_end(&mut vec![]).unwrap();
+
+    if count == 0 {
+        Ok(value)
+    } else {
+        Err(Error::TrailingBytes {
+            field: field_name.to_string(),
+            count,
+        })
+    }
+}
+
+/// Map access over all entries in a table, apart from reserved keys.
+struct TableMapperEntries<'a, const W: bool, C> {
+    table: &'a TableMapper<'a, W, C>,
+    entries: std::vec::IntoIter<(String, Box<[u8]>)>,
+    value: Option<(String, Box<[u8]>)>,
+}
 
-        let bytes = self.table.db.get(self.table.cf, field_name)?;
+impl<'a, const W: bool, C> TableMapperEntries<'a, W, C> {
+    fn new(table: &'a TableMapper<'a, W, C>) -> Result<Self, Error> {
+        let mut entries = vec![];
 
-        match bytes {
-            Some(bytes) => {
-                let mut deserializer = OwnedSerdeDecoder::from_reader(
-                    BufReader::new(Cursor::new(bytes)),
-                    self.table.bincode_config,
-                );
+        for (key, value) in table.entries()? {
+            let key = String::from_utf8(key.into_vec()).map_err(|error| {
+                <Error as serde::de::Error>::invalid_value(
+                    serde::de::Unexpected::Bytes(error.as_bytes()),
+                    &"a UTF-8 key",
+                )
+            })?;
 
-                seed.deserialize(deserializer.as_deserializer())
-                    .map_err(Error::Decoding)
-            }
-            None => {
-                let mut deserializer = OwnedSerdeDecoder::from_reader(
-                    BufReader::new(Cursor::new(BINCODE_NONE_BYTES)),
-                    self.table.bincode_config,
-                );
-
-                seed.deserialize(deserializer.as_deserializer())
-                    .map_err(Error::Decoding)
+            entries.push((key, value));
+        }
+
+        Ok(Self {
+            table,
+            entries: entries.into_iter(),
+            value: None,
+        })
+    }
+}
+
+impl<'a, 'de: 'a, const W: bool, C: bincode::config::Config> serde::de::MapAccess<'de>
+    for TableMapperEntries<'a, W, C>
+{
+    type Error = Error;
+
+    fn next_key_seed<K: serde::de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                let deserializer = serde::de::value::StrDeserializer::<Error>::new(&key);
+                let key_value = seed.deserialize(deserializer)?;
+                self.value = Some((key, value));
+
+                Ok(Some(key_value))
             }
+            None => Ok(None),
         }
     }
+
+    fn next_value_seed<V: serde::de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (key, bytes) = self.value.take().ok_or_else(|| {
+            <Error as serde::de::Error>::custom("next_value called before next_key")
+        })?;
+
+        decode_field(seed, &bytes, self.table.bincode_config, &key)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct TableMapperEnumAccess<'a, const W: bool, C> {
+    table: &'a TableMapper<'a, W, C>,
+    variant: String,
+}
+
+impl<'a, 'de: 'a, const W: bool, C: bincode::config::Config> serde::de::EnumAccess<'de>
+    for TableMapperEnumAccess<'a, W, C>
+{
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: serde::de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let deserializer = serde::de::value::StrDeserializer::new(&self.variant);
+
+        seed.deserialize(deserializer).map(|value| (value, self))
+    }
+}
+
+impl<'a, 'de: 'a, const W: bool, C: bincode::config::Config> serde::de::VariantAccess<'de>
+    for TableMapperEnumAccess<'a, W, C>
+{
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: serde::de::DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, Self::Error> {
+        seed.deserialize(self.table)
+    }
+
+    fn tuple_variant<V: serde::de::Visitor<'de>>(
+        self,
+        _len: usize,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        Err(Error::Unsupported)
+    }
+
+    fn struct_variant<V: serde::de::Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(TableMapperAccess::new(self.table, fields)?)
+    }
 }
 
 #[cfg(test)]
@@ -685,6 +1276,25 @@ mod tests {
     use quickcheck_arbitrary_derive::QuickCheck;
     use serde::{de::Deserialize, ser::Serialize};
 
+    fn open_test_db(cf_names: &[&str]) -> (tempfile::TempDir, crate::wrapper::Db) {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+
+        let db = rocksdb::OptimisticTransactionDB::open_cf_descriptors(
+            &options,
+            &test_db_dir,
+            cf_names.iter().map(|cf_name| {
+                rocksdb::ColumnFamilyDescriptor::new(*cf_name, rocksdb::Options::default())
+            }),
+        )
+        .unwrap();
+
+        (test_db_dir, db.into())
+    }
+
     #[derive(
         Clone, Debug, Eq, PartialEq, QuickCheck, serde_derive::Deserialize, serde_derive::Serialize,
     )]
@@ -696,21 +1306,7 @@ mod tests {
 
     #[quickcheck_macros::quickcheck]
     fn round_trip_test(test: Test, new_foo: String) -> bool {
-        let mut options = rocksdb::Options::default();
-        options.create_if_missing(true);
-        options.create_missing_column_families(true);
-
-        let db = rocksdb::OptimisticTransactionDB::open_cf_descriptors(
-            &options,
-            tempfile::tempdir().unwrap(),
-            vec![rocksdb::ColumnFamilyDescriptor::new(
-                "test",
-                rocksdb::Options::default(),
-            )],
-        )
-        .unwrap();
-
-        let wrapper = crate::wrapper::Db::from(db);
+        let (_test_db_dir, wrapper) = open_test_db(&["test"]);
 
         let mapper = super::TableMapper::new(
             &wrapper,
@@ -749,4 +1345,302 @@ mod tests {
 
         read_test == test && new_read_test == new_test
     }
+
+    #[test]
+    fn field_decoding_error() {
+        let (_test_db_dir, wrapper) = open_test_db(&["test"]);
+        let cf = wrapper.handle("test").unwrap();
+
+        let test = Test {
+            foo: "foo".to_string(),
+            bar: vec![Some(1), None],
+            qux: true,
+        };
+
+        test.serialize(super::TableMapper::new(
+            &wrapper,
+            cf,
+            bincode::config::standard(),
+        ))
+        .unwrap();
+
+        // Not a valid Bincode encoding of a `bool`.
+        wrapper.put(cf, "qux", [7]).unwrap();
+
+        let mapper = super::TableMapper::<true, _>::new(&wrapper, cf, bincode::config::standard());
+
+        match Test::deserialize(&mapper) {
+            Err(super::Error::FieldDecoding { field, .. }) => assert_eq!(field, "qux"),
+            other => panic!("Unexpected result: {other:?}"),
+        }
+    }
+
+    #[derive(Debug, Eq, PartialEq, serde_derive::Deserialize, serde_derive::Serialize)]
+    struct Extended {
+        foo: String,
+        bar: Vec<Option<u64>>,
+        qux: bool,
+        added: Option<String>,
+    }
+
+    #[derive(Debug, serde_derive::Deserialize)]
+    #[allow(dead_code)]
+    struct Required {
+        foo: String,
+        count: u64,
+    }
+
+    #[test]
+    fn missing_fields() {
+        let (_test_db_dir, wrapper) = open_test_db(&["test"]);
+        let cf = wrapper.handle("test").unwrap();
+
+        let test = Test {
+            foo: "foo".to_string(),
+            bar: vec![Some(1), None],
+            qux: true,
+        };
+
+        test.serialize(super::TableMapper::new(
+            &wrapper,
+            cf,
+            bincode::config::standard(),
+        ))
+        .unwrap();
+
+        let mapper = super::TableMapper::<true, _>::new(&wrapper, cf, bincode::config::standard());
+
+        assert_eq!(
+            Extended::deserialize(&mapper).unwrap(),
+            Extended {
+                foo: test.foo,
+                bar: test.bar,
+                qux: test.qux,
+                added: None,
+            }
+        );
+
+        match Required::deserialize(&mapper) {
+            Err(super::Error::MissingStoredField(field)) => assert_eq!(field, "count"),
+            other => panic!("Unexpected result: {other:?}"),
+        }
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn option_round_trip(test: Test) -> bool {
+        let (_test_db_dir, wrapper) = open_test_db(&["test"]);
+        let cf = wrapper.handle("test").unwrap();
+
+        [None, Some(test.clone()), None, Some(test)]
+            .into_iter()
+            .all(|value| {
+                value
+                    .serialize(super::TableMapper::new(
+                        &wrapper,
+                        cf,
+                        bincode::config::standard(),
+                    ))
+                    .unwrap();
+
+                let mapper =
+                    super::TableMapper::<true, _>::new(&wrapper, cf, bincode::config::standard());
+
+                Option::<Test>::deserialize(&mapper).unwrap() == value
+            })
+    }
+
+    #[derive(
+        Clone, Debug, Eq, PartialEq, QuickCheck, serde_derive::Deserialize, serde_derive::Serialize,
+    )]
+    struct Renamed {
+        #[serde(rename = "first")]
+        foo: String,
+        bar: u64,
+    }
+
+    #[derive(
+        Clone, Debug, Eq, PartialEq, QuickCheck, serde_derive::Deserialize, serde_derive::Serialize,
+    )]
+    #[serde(rename_all = "camelCase")]
+    struct RenamedAll {
+        last_scrape_ms: u64,
+        is_enabled: bool,
+    }
+
+    fn stored_keys(wrapper: &crate::wrapper::Db, cf: &rocksdb::ColumnFamily) -> Vec<String> {
+        wrapper
+            .iterator(cf, rocksdb::IteratorMode::Start)
+            .map(|result| String::from_utf8(result.unwrap().0.into_vec()).unwrap())
+            .collect()
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn renamed_round_trip(renamed: Renamed, renamed_all: RenamedAll) -> bool {
+        let (_test_db_dir, wrapper) = open_test_db(&["renamed", "renamed_all"]);
+        let renamed_cf = wrapper.handle("renamed").unwrap();
+        let renamed_all_cf = wrapper.handle("renamed_all").unwrap();
+
+        renamed
+            .serialize(super::TableMapper::new(
+                &wrapper,
+                renamed_cf,
+                bincode::config::standard(),
+            ))
+            .unwrap();
+        renamed_all
+            .serialize(super::TableMapper::new(
+                &wrapper,
+                renamed_all_cf,
+                bincode::config::standard(),
+            ))
+            .unwrap();
+
+        let renamed_mapper =
+            super::TableMapper::<false, _>::new(&wrapper, renamed_cf, bincode::config::standard());
+        let renamed_all_mapper = super::TableMapper::<false, _>::new(
+            &wrapper,
+            renamed_all_cf,
+            bincode::config::standard(),
+        );
+
+        stored_keys(&wrapper, renamed_cf) == ["bar", "first"]
+            && stored_keys(&wrapper, renamed_all_cf) == ["isEnabled", "lastScrapeMs"]
+            && Renamed::deserialize(&renamed_mapper).unwrap() == renamed
+            && RenamedAll::deserialize(&renamed_all_mapper).unwrap() == renamed_all
+    }
+
+    #[derive(Debug, Eq, PartialEq, serde_derive::Deserialize)]
+    struct Defaulted {
+        foo: String,
+        #[serde(default)]
+        count: u32,
+        #[serde(default = "default_label")]
+        label: String,
+    }
+
+    fn default_label() -> String {
+        "label".to_string()
+    }
+
+    #[test]
+    fn default_fields() {
+        let (_test_db_dir, wrapper) = open_test_db(&["test"]);
+        let cf = wrapper.handle("test").unwrap();
+
+        let test = Test {
+            foo: "foo".to_string(),
+            bar: vec![],
+            qux: false,
+        };
+
+        test.serialize(super::TableMapper::new(
+            &wrapper,
+            cf,
+            bincode::config::standard(),
+        ))
+        .unwrap();
+
+        let mapper = super::TableMapper::<false, _>::new(&wrapper, cf, bincode::config::standard());
+
+        assert_eq!(
+            Defaulted::deserialize(&mapper).unwrap(),
+            Defaulted {
+                foo: test.foo,
+                count: 0,
+                label: default_label(),
+            }
+        );
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn map_round_trip(
+        map: std::collections::HashMap<String, u64>,
+        new_map: std::collections::HashMap<String, u64>,
+    ) -> bool {
+        let (_test_db_dir, wrapper) = open_test_db(&["test"]);
+        let cf = wrapper.handle("test").unwrap();
+
+        [map, new_map].into_iter().all(|map| {
+            map.serialize(super::TableMapper::new(
+                &wrapper,
+                cf,
+                bincode::config::standard(),
+            ))
+            .unwrap();
+
+            let mapper =
+                super::TableMapper::<false, _>::new(&wrapper, cf, bincode::config::standard());
+
+            std::collections::HashMap::<String, u64>::deserialize(&mapper).unwrap() == map
+        })
+    }
+
+    #[test]
+    fn reserved_map_keys() {
+        let (_test_db_dir, wrapper) = open_test_db(&["test"]);
+        let cf = wrapper.handle("test").unwrap();
+
+        for reserved_key in crate::RESERVED_KEYS {
+            let map = std::collections::BTreeMap::from([("foo", 1u64), (reserved_key, 2u64)]);
+
+            match map.serialize(super::TableMapper::new(
+                &wrapper,
+                cf,
+                bincode::config::standard(),
+            )) {
+                Err(super::Error::ReservedKey(key)) => assert_eq!(key, reserved_key),
+                other => panic!("Unexpected result: {other:?}"),
+            }
+        }
+
+        // Nothing is written when a map is rejected.
+        assert!(stored_keys(&wrapper, cf).is_empty());
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let (_test_db_dir, wrapper) = open_test_db(&["test"]);
+        let cf = wrapper.handle("test").unwrap();
+
+        let test = Test {
+            foo: "foo".to_string(),
+            bar: vec![Some(1)],
+            qux: true,
+        };
+
+        test.serialize(super::TableMapper::new(
+            &wrapper,
+            cf,
+            bincode::config::standard(),
+        ))
+        .unwrap();
+
+        let mut qux = bincode::serde::encode_to_vec(true, bincode::config::standard()).unwrap();
+        qux.extend([0, 1]);
+        wrapper.put(cf, "qux", qux).unwrap();
+
+        let mapper = super::TableMapper::<false, _>::new(&wrapper, cf, bincode::config::standard());
+
+        match Test::deserialize(&mapper) {
+            Err(super::Error::TrailingBytes { field, count }) => {
+                assert_eq!(field, "qux");
+                assert_eq!(count, 2);
+            }
+            other => panic!("Unexpected result: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_source() {
+        use std::error::Error;
+
+        let decode_error =
+            bincode::serde::decode_from_slice::<bool, _>(&[7], bincode::config::standard())
+                .unwrap_err();
+
+        assert!(super::Error::Decoding(decode_error).source().is_some());
+        assert!(<super::Error as serde::de::Error>::custom("foo")
+            .source()
+            .is_some());
+    }
 }
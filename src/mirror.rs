@@ -0,0 +1,167 @@
+use crate::{mapper, Database};
+
+/// A writeable database whose config and books writes are also applied to a second database.
+///
+/// Reads come from the primary database.
+pub struct Mirrored<C, B> {
+    primary: Database<true, C, B>,
+    mirror: Database<true, C, B>,
+}
+
+impl<C, B> Database<true, C, B> {
+    /// Mirror config and books writes made through the returned wrapper to another database.
+    pub fn with_mirror(self, mirror: Database<true, C, B>) -> Mirrored<C, B> {
+        Mirrored {
+            primary: self,
+            mirror,
+        }
+    }
+}
+
+impl<C, B> Mirrored<C, B> {
+    pub fn primary(&self) -> &Database<true, C, B> {
+        &self.primary
+    }
+
+    pub fn mirror(&self) -> &Database<true, C, B> {
+        &self.mirror
+    }
+
+    pub fn into_inner(self) -> (Database<true, C, B>, Database<true, C, B>) {
+        (self.primary, self.mirror)
+    }
+}
+
+impl<C, B> Mirrored<C, B>
+where
+    C: serde::ser::Serialize + serde::de::DeserializeOwned,
+    B: serde::ser::Serialize + serde::de::DeserializeOwned,
+{
+    pub fn read_config(&self) -> Result<C, mapper::Error> {
+        self.primary.read_config()
+    }
+
+    pub fn read_books(&self) -> Result<B, mapper::Error> {
+        self.primary.read_books()
+    }
+
+    /// Write the config to both databases.
+    ///
+    /// If the write to the mirror fails, the previous config is restored in the primary on a
+    /// best-effort basis, and the mirror's error is returned.
+    pub fn write_config(&self, config: &C) -> Result<(), mapper::Error> {
+        let previous = self.primary.read_config()?;
+
+        self.primary.write_config(config)?;
+        self.mirror.write_config(config).inspect_err(|_| {
+            // There is no way to report a failure here, so we ignore it.
+            let _ = self.primary.write_config(&previous);
+        })
+    }
+
+    /// Write the books to both databases.
+    ///
+    /// If the write to the mirror fails, the previous books are restored in the primary on a
+    /// best-effort basis, and the mirror's error is returned.
+    pub fn write_books(&self, books: &B) -> Result<(), mapper::Error> {
+        let previous = self.primary.read_books()?;
+
+        self.primary.write_books(books)?;
+        self.mirror.write_books(books).inspect_err(|_| {
+            // There is no way to report a failure here, so we ignore it.
+            let _ = self.primary.write_books(&previous);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck_arbitrary_derive::QuickCheck;
+    use serde::ser::{SerializeStruct, Serializer};
+
+    #[derive(
+        Clone, Debug, Eq, PartialEq, QuickCheck, serde_derive::Deserialize, serde_derive::Serialize,
+    )]
+    struct Config {
+        case_sensitive: bool,
+        limit: u64,
+    }
+
+    #[derive(
+        Clone, Debug, Eq, PartialEq, QuickCheck, serde_derive::Deserialize, serde_derive::Serialize,
+    )]
+    struct Books {
+        last_scrape_ms: u64,
+        region: String,
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn mirrored_writes(config: Config, books: Books, new_config: Config, new_books: Books) -> bool {
+        let primary_dir = tempfile::tempdir().unwrap();
+        let mirror_dir = tempfile::tempdir().unwrap();
+
+        let create = |path: &std::path::Path| {
+            crate::Database::create(
+                path,
+                vec![],
+                Default::default(),
+                true,
+                config.clone(),
+                books.clone(),
+            )
+            .unwrap()
+        };
+
+        let mirrored = create(primary_dir.path()).with_mirror(create(mirror_dir.path()));
+
+        mirrored.write_config(&new_config).unwrap();
+        mirrored.write_books(&new_books).unwrap();
+
+        mirrored.read_config().unwrap() == new_config
+            && mirrored.read_books().unwrap() == new_books
+            && mirrored.mirror().read_config().unwrap() == new_config
+            && mirrored.mirror().read_books().unwrap() == new_books
+    }
+
+    #[test]
+    fn mirror_failure() {
+        let primary_dir = tempfile::tempdir().unwrap();
+        let mirror_dir = tempfile::tempdir().unwrap();
+
+        let config = Config {
+            case_sensitive: false,
+            limit: 10,
+        };
+        let new_config = Config {
+            case_sensitive: true,
+            limit: 20,
+        };
+
+        let create = |path: &std::path::Path| {
+            crate::Database::create(path, vec![], Default::default(), true, config.clone(), ())
+                .unwrap()
+        };
+
+        let mirror = create(mirror_dir.path());
+        let mirrored = create(primary_dir.path()).with_mirror(mirror.clone());
+
+        // Holding an uncommitted mapper write on the mirror makes its next config write fail.
+        let mapper = mirror
+            .table_mapper(crate::CONFIG_CF_NAME, bincode::config::standard())
+            .unwrap();
+        let mut outstanding = mapper.serialize_struct("Config", 2).unwrap();
+        outstanding
+            .serialize_field("case_sensitive", &true)
+            .unwrap();
+
+        assert!(matches!(
+            mirrored.write_config(&new_config),
+            Err(crate::mapper::Error::InvalidTransaction)
+        ));
+
+        drop(outstanding);
+
+        assert_eq!(mirrored.read_config().unwrap(), config);
+        assert_eq!(mirrored.mirror().read_config().unwrap(), config);
+    }
+}
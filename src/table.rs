@@ -0,0 +1,129 @@
+use crate::{mapper, wrapper::Db, wrapper::KEY_BINCODE_CONFIG};
+use rocksdb::{ColumnFamily, IteratorMode};
+use serde::de::DeserializeOwned;
+
+/// Typed access to a column family that is not managed by the crate.
+///
+/// Keys are encoded in the same way as [`Db::put_typed`] keys, and values use the database's
+/// endianness.
+pub struct Table<'a> {
+    db: &'a Db,
+    cf: &'a ColumnFamily,
+}
+
+impl<'a> Table<'a> {
+    pub(crate) fn new(db: &'a Db, cf: &'a ColumnFamily) -> Self {
+        Self { db, cf }
+    }
+
+    /// Read a value written with [`Table::put_typed`].
+    pub fn get_typed<K: serde::ser::Serialize, V: DeserializeOwned>(
+        &self,
+        key: &K,
+    ) -> Result<Option<V>, mapper::Error> {
+        with_bincode_config!(self.db.endianness(), bincode_config => {
+            self.db.get_typed(self.cf, key, bincode_config)
+        })
+    }
+
+    /// Write a value under a typed key.
+    ///
+    /// This fails for read-only databases.
+    pub fn put_typed<K: serde::ser::Serialize, V: serde::ser::Serialize>(
+        &self,
+        key: &K,
+        value: &V,
+    ) -> Result<(), mapper::Error> {
+        with_bincode_config!(self.db.endianness(), bincode_config => {
+            self.db.put_typed(self.cf, key, value, bincode_config)
+        })
+    }
+
+    /// Iterate over the table, decoding keys and values as they were written by
+    /// [`Table::put_typed`].
+    ///
+    /// An entry that cannot be decoded is returned as an error item carrying the raw key, and
+    /// iteration continues with the next entry.
+    pub fn iterator<K: DeserializeOwned, V: DeserializeOwned>(
+        &self,
+        mode: IteratorMode,
+    ) -> impl Iterator<Item = Result<(K, V), mapper::Error>> + use<'a, K, V> {
+        let endianness = self.db.endianness();
+
+        self.db.decoding_iterator(self.cf, mode, move |key, value| {
+            let (key, _) = bincode::serde::decode_from_slice(key, KEY_BINCODE_CONFIG)?;
+
+            let (value, _) = with_bincode_config!(endianness, bincode_config => {
+                bincode::serde::decode_from_slice(value, bincode_config)?
+            });
+
+            Ok((key, value))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn tables() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let db = crate::Database::create(
+            &test_db_dir,
+            ["users", "scores"]
+                .map(|cf_name| rocksdb::ColumnFamilyDescriptor::new(cf_name, Default::default()))
+                .into(),
+            Default::default(),
+            true,
+            (),
+            (),
+        )
+        .unwrap();
+
+        let users = db.table("users").unwrap();
+        let scores = db.table("scores").unwrap();
+
+        for (id, name) in [(2u64, "bar"), (1, "foo"), (300, "qux")] {
+            users.put_typed(&id, &name.to_string()).unwrap();
+            scores.put_typed(&name.to_string(), &(id * 10)).unwrap();
+        }
+
+        assert_eq!(
+            users.get_typed::<_, String>(&1u64).unwrap(),
+            Some("foo".to_string())
+        );
+        assert_eq!(users.get_typed::<_, String>(&3u64).unwrap(), None);
+        assert_eq!(
+            scores.get_typed::<_, u64>(&"qux".to_string()).unwrap(),
+            Some(3000)
+        );
+
+        assert_eq!(
+            users
+                .iterator::<u64, String>(rocksdb::IteratorMode::Start)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![
+                (1, "foo".to_string()),
+                (2, "bar".to_string()),
+                (300, "qux".to_string())
+            ]
+        );
+        assert_eq!(
+            scores
+                .iterator::<String, u64>(rocksdb::IteratorMode::End)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![
+                ("qux".to_string(), 3000),
+                ("foo".to_string(), 10),
+                ("bar".to_string(), 20)
+            ]
+        );
+
+        assert!(matches!(
+            db.table("missing"),
+            Err(crate::error::Error::UnknownColumnFamily(cf_name)) if cf_name == "missing"
+        ));
+    }
+}
@@ -1,72 +1,486 @@
+use crate::{error::Error, mapper, Endianness};
 use rocksdb::{
-    ColumnFamily, DBPinnableSlice, IteratorMode, OptimisticTransactionDB, TransactionDB, DB,
+    checkpoint::Checkpoint, ColumnFamily, DBAccess, DBPinnableSlice, DBRawIteratorWithThreadMode,
+    Direction, IngestExternalFileOptions, IteratorMode, OptimisticTransactionDB,
+    OptimisticTransactionOptions, ReadOptions, SnapshotWithThreadMode, TransactionDB,
+    TransactionOptions, WriteBatch, WriteBatchWithTransaction, WriteOptions, DB,
 };
-use std::sync::Arc;
+use serde::de::DeserializeOwned;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+use std::thread::ThreadId;
 
-type KeyValuePair = (Box<[u8]>, Box<[u8]>);
+pub(crate) type KeyValuePair = (Box<[u8]>, Box<[u8]>);
+
+/// A page of entries, and the cursor for the next page if there are more entries.
+pub type Page = (Vec<KeyValuePair>, Option<Box<[u8]>>);
+
+/// Bincode configuration for typed keys, which uses fixed-width big-endian integers so that the
+/// byte order of keys made up of unsigned integers matches their numeric order.
+pub(crate) const KEY_BINCODE_CONFIG: bincode::config::Configuration<
+    bincode::config::BigEndian,
+    bincode::config::Fixint,
+> = bincode::config::standard()
+    .with_big_endian()
+    .with_fixed_int_encoding();
+
+fn cf_key(cf: &ColumnFamily) -> usize {
+    cf as *const ColumnFamily as usize
+}
+
+fn has_merge_operator(merge_operator_cfs: &RwLock<HashSet<usize>>, cf: &ColumnFamily) -> bool {
+    merge_operator_cfs.read().unwrap().contains(&cf_key(cf))
+}
+
+/// Identifies an uncommitted table mapper write by thread and column family.
+pub(crate) type MapperWriteSlot = (ThreadId, usize);
+
+/// Callback invoked with the column family name and total value size of each committed write.
+pub type OnWrite = Box<dyn Fn(&str, usize) + Send + Sync>;
+
+// Shared so that the callback can be invoked without holding the lock.
+type SharedOnWrite = Arc<dyn Fn(&str, usize) + Send + Sync>;
 
 enum DbInner {
     ReadOnly(DB),
+    ReadWrite(DB),
     OptimisticTransaction(OptimisticTransactionDB),
     PessimisticTransaction(TransactionDB),
 }
 
-/// Simple abstraction over read-only and transactional writeable databases.
+struct DbState {
+    inner: DbInner,
+    cf_names: Vec<String>,
+    // This lives here rather than on `Database` because the table helpers only have the `Db`, and
+    // every clone of a handle must decode the managed tables the same way.
+    endianness: Endianness,
+    // Column families are keyed by handle address, which is stable for the life of the database.
+    merge_operator_cfs: RwLock<HashSet<usize>>,
+    on_write: RwLock<Option<SharedOnWrite>>,
+    flush_on_drop: AtomicBool,
+    serialize_writes: AtomicBool,
+    write_lock: Mutex<()>,
+    mapper_writes: Mutex<HashSet<MapperWriteSlot>>,
+}
+
+impl From<DbInner> for DbState {
+    fn from(value: DbInner) -> Self {
+        Self {
+            inner: value,
+            cf_names: vec![],
+            endianness: Endianness::default(),
+            merge_operator_cfs: RwLock::default(),
+            on_write: RwLock::default(),
+            flush_on_drop: AtomicBool::new(false),
+            serialize_writes: AtomicBool::new(false),
+            write_lock: Mutex::default(),
+            mapper_writes: Mutex::default(),
+        }
+    }
+}
+
+impl DbState {
+    fn flush(&self) -> Result<(), Error> {
+        // The Rust bindings do not support flushing pessimistic transaction databases.
+        match &self.inner {
+            DbInner::ReadWrite(db) => {
+                for cf_name in &self.cf_names {
+                    if let Some(cf) = db.cf_handle(cf_name) {
+                        db.flush_cf(cf)?;
+                    }
+                }
+            }
+            DbInner::OptimisticTransaction(db) => {
+                for cf_name in &self.cf_names {
+                    if let Some(cf) = db.cf_handle(cf_name) {
+                        db.flush_cf(cf)?;
+                    }
+                }
+            }
+            DbInner::ReadOnly(_) | DbInner::PessimisticTransaction(_) => {
+                return Err(Error::Unsupported)
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for DbState {
+    fn drop(&mut self) {
+        if self.flush_on_drop.load(Ordering::Acquire) {
+            // There is no way to report a failure here, so we ignore it.
+            let _ = self.flush();
+        }
+    }
+}
+
+/// Simple abstraction over read-only, plain writeable, and transactional writeable databases.
 #[derive(Clone)]
-pub struct Db(Arc<DbInner>);
+pub struct Db(Arc<DbState>);
 
 impl From<DB> for Db {
     fn from(value: DB) -> Self {
-        Self(Arc::new(DbInner::ReadOnly(value)))
+        Self(Arc::new(DbInner::ReadOnly(value).into()))
     }
 }
 
 impl From<OptimisticTransactionDB> for Db {
     fn from(value: OptimisticTransactionDB) -> Self {
-        Self(Arc::new(DbInner::OptimisticTransaction(value)))
+        Self(Arc::new(DbInner::OptimisticTransaction(value).into()))
     }
 }
 
 impl From<TransactionDB> for Db {
     fn from(value: TransactionDB) -> Self {
-        Self(Arc::new(DbInner::PessimisticTransaction(value)))
+        Self(Arc::new(DbInner::PessimisticTransaction(value).into()))
+    }
+}
+
+impl std::fmt::Debug for Db {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mode = match &self.0.inner {
+            DbInner::ReadOnly(_) => "ReadOnly",
+            DbInner::ReadWrite(_) => "ReadWrite",
+            DbInner::OptimisticTransaction(_) => "OptimisticTransaction",
+            DbInner::PessimisticTransaction(_) => "PessimisticTransaction",
+        };
+
+        f.debug_struct("Db")
+            .field("mode", &mode)
+            .field("cf_names", &self.0.cf_names)
+            .finish()
     }
 }
 
 impl Db {
+    /// Wrap a plain database that was opened for writing.
+    ///
+    /// Writes made through [`Db::transaction`] are collected in a write batch and applied atomically
+    /// on commit.
+    pub fn read_write(db: DB) -> Self {
+        Self(Arc::new(DbInner::ReadWrite(db).into()))
+    }
+
+    // For internal use only.
+    pub(super) fn with_cf_names(mut self, cf_names: Vec<String>) -> Self {
+        // Safe because this is only called on newly constructed wrappers.
+        Arc::get_mut(&mut self.0).unwrap().cf_names = cf_names;
+        self
+    }
+
+    // For internal use only.
+    pub(super) fn with_endianness(mut self, endianness: Endianness) -> Self {
+        // Safe because this is only called on newly constructed wrappers.
+        Arc::get_mut(&mut self.0).unwrap().endianness = endianness;
+        self
+    }
+
+    /// The endianness used to encode values in the managed tables.
+    pub fn endianness(&self) -> Endianness {
+        self.0.endianness
+    }
+
     // For internal use only.
     pub(super) fn read_only(&self) -> Option<&DB> {
-        match self.0.as_ref() {
+        match &self.0.inner {
             DbInner::ReadOnly(inner) => Some(inner),
             _ => None,
         }
     }
 
+    /// The underlying database, if it uses optimistic transactions.
+    pub fn as_optimistic(&self) -> Option<&OptimisticTransactionDB> {
+        match &self.0.inner {
+            DbInner::OptimisticTransaction(inner) => Some(inner),
+            _ => None,
+        }
+    }
+
+    /// The underlying database, if it uses pessimistic transactions.
+    pub fn as_pessimistic(&self) -> Option<&TransactionDB> {
+        match &self.0.inner {
+            DbInner::PessimisticTransaction(inner) => Some(inner),
+            _ => None,
+        }
+    }
+
     pub fn transaction(&self) -> Option<Transaction<'_>> {
-        match self.0.as_ref() {
+        match &self.0.inner {
+            DbInner::ReadOnly(_) => None,
+            DbInner::ReadWrite(db) => Some(TransactionInner::Batch(db, RefCell::default())),
+            DbInner::OptimisticTransaction(db) => {
+                Some(TransactionInner::Optimistic(db.transaction()))
+            }
+            DbInner::PessimisticTransaction(db) => {
+                Some(TransactionInner::Pessimistic(db.transaction()))
+            }
+        }
+        .map(|inner| self.wrap_transaction(inner))
+    }
+
+    /// Begin a transaction whose writes use the given options when committed.
+    ///
+    /// This is useful for bulk writers that should set `low_pri` or `no_slowdown`.
+    pub fn transaction_with_options(&self, write_options: WriteOptions) -> Option<Transaction<'_>> {
+        match &self.0.inner {
+            DbInner::ReadOnly(_) => None,
+            DbInner::ReadWrite(db) => Some(TransactionInner::Batch(
+                db,
+                RefCell::new(PendingBatch {
+                    write_options,
+                    ..Default::default()
+                }),
+            )),
+            DbInner::OptimisticTransaction(db) => Some(TransactionInner::Optimistic(
+                db.transaction_opt(&write_options, &OptimisticTransactionOptions::default()),
+            )),
+            DbInner::PessimisticTransaction(db) => Some(TransactionInner::Pessimistic(
+                db.transaction_opt(&write_options, &TransactionOptions::default()),
+            )),
+        }
+        .map(|inner| self.wrap_transaction(inner))
+    }
+
+    /// Begin an optimistic transaction with the given options.
+    ///
+    /// Returns `None` unless this is an optimistic transaction database.
+    pub fn transaction_opt(
+        &self,
+        transaction_options: &OptimisticTransactionOptions,
+    ) -> Option<Transaction<'_>> {
+        match &self.0.inner {
+            DbInner::OptimisticTransaction(db) => Some(TransactionInner::Optimistic(
+                db.transaction_opt(&WriteOptions::default(), transaction_options),
+            )),
+            _ => None,
+        }
+        .map(|inner| self.wrap_transaction(inner))
+    }
+
+    /// Begin a pessimistic transaction with the given options.
+    ///
+    /// Returns `None` unless this is a pessimistic transaction database.
+    pub fn pessimistic_transaction_opt(
+        &self,
+        transaction_options: &TransactionOptions,
+    ) -> Option<Transaction<'_>> {
+        match &self.0.inner {
+            DbInner::PessimisticTransaction(db) => Some(TransactionInner::Pessimistic(
+                db.transaction_opt(&WriteOptions::default(), transaction_options),
+            )),
+            _ => None,
+        }
+        .map(|inner| self.wrap_transaction(inner))
+    }
+
+    /// Begin a transaction that takes a snapshot immediately.
+    ///
+    /// Conflict detection then uses the start of the transaction as its baseline for every key,
+    /// instead of the first read or write of each key. The Rust bindings do not support resetting
+    /// the snapshot once the transaction has begun. Write batches do not use snapshots.
+    pub fn transaction_with_snapshot(&self) -> Option<Transaction<'_>> {
+        match &self.0.inner {
             DbInner::ReadOnly(_) => None,
-            DbInner::OptimisticTransaction(db) => Some(Transaction::Optimistic(db.transaction())),
-            DbInner::PessimisticTransaction(db) => Some(Transaction::Pessimistic(db.transaction())),
+            DbInner::ReadWrite(db) => Some(TransactionInner::Batch(db, RefCell::default())),
+            DbInner::OptimisticTransaction(db) => {
+                let mut transaction_options = OptimisticTransactionOptions::default();
+                transaction_options.set_snapshot(true);
+
+                Some(TransactionInner::Optimistic(db.transaction_opt(
+                    &WriteOptions::default(),
+                    &transaction_options,
+                )))
+            }
+            DbInner::PessimisticTransaction(db) => {
+                let mut transaction_options = TransactionOptions::default();
+                transaction_options.set_snapshot(true);
+
+                Some(TransactionInner::Pessimistic(db.transaction_opt(
+                    &WriteOptions::default(),
+                    &transaction_options,
+                )))
+            }
         }
+        .map(|inner| self.wrap_transaction(inner))
     }
 
     pub fn handle(&self, name: &str) -> Option<&ColumnFamily> {
-        match self.0.as_ref() {
+        match &self.0.inner {
             DbInner::ReadOnly(db) => db.cf_handle(name),
+            DbInner::ReadWrite(db) => db.cf_handle(name),
             DbInner::OptimisticTransaction(db) => db.cf_handle(name),
             DbInner::PessimisticTransaction(db) => db.cf_handle(name),
         }
     }
 
+    /// Return an owned handle for the named column family that can be stored alongside (or
+    /// instead of) a clone of this wrapper.
+    ///
+    /// The handle keeps the underlying database open, so the column family reference it provides
+    /// remains valid for as long as the handle itself.
+    pub fn cf_arc(&self, name: &str) -> Option<BoundColumnFamily> {
+        self.handle(name).map(|_| BoundColumnFamily {
+            db: self.clone(),
+            name: name.to_string(),
+        })
+    }
+
+    /// Flush all known column families.
+    ///
+    /// This fails with [`Error::Unsupported`] for read-only and pessimistic transaction databases.
+    pub fn flush(&self) -> Result<(), Error> {
+        self.0.flush()
+    }
+
+    /// Flush all known column families when the last clone of this wrapper is dropped.
+    ///
+    /// This currently has no effect for read-only and pessimistic transaction databases.
+    pub fn set_flush_on_drop(&self, flush_on_drop: bool) {
+        self.0.flush_on_drop.store(flush_on_drop, Ordering::Release);
+    }
+
+    /// Serialize table writes made through this wrapper and its clones.
+    ///
+    /// This reduces conflicts between concurrent writers at the cost of parallelism.
+    pub fn set_serialize_writes(&self, serialize_writes: bool) {
+        self.0
+            .serialize_writes
+            .store(serialize_writes, Ordering::Release);
+    }
+
+    // For internal use only: claims the slot for an uncommitted table mapper write to the column
+    // family on the current thread, returning `None` if it is already taken.
+    pub(super) fn begin_mapper_write(&self, cf: &ColumnFamily) -> Option<MapperWriteSlot> {
+        let slot = (std::thread::current().id(), cf_key(cf));
+
+        self.0
+            .mapper_writes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(slot)
+            .then_some(slot)
+    }
+
+    // For internal use only.
+    pub(super) fn end_mapper_write(&self, slot: MapperWriteSlot) {
+        self.0
+            .mapper_writes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&slot);
+    }
+
+    // For internal use only.
+    pub(super) fn write_guard(&self) -> Option<MutexGuard<'_, ()>> {
+        if self.0.serialize_writes.load(Ordering::Acquire) {
+            Some(
+                self.0
+                    .write_lock
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner),
+            )
+        } else {
+            None
+        }
+    }
+
+    /// Install or remove a callback that is invoked after each successful write through this
+    /// wrapper.
+    ///
+    /// The callback only fires for column families whose names are known to the wrapper, and
+    /// receives the total size of the values written to the column family by the commit. Writes
+    /// into caller-provided transactions are not reported. The callback may itself replace or
+    /// remove the callback.
+    pub fn set_on_write(&self, on_write: Option<OnWrite>) {
+        *self.0.on_write.write().unwrap() = on_write.map(Arc::from);
+    }
+
+    // For internal use only: reports a committed write of the given total value size.
+    pub(super) fn record_write(&self, cf: &ColumnFamily, bytes: usize) {
+        let on_write = self.0.on_write.read().unwrap().clone();
+
+        if let Some(on_write) = on_write {
+            if let Some(cf_name) = self.cf_name(cf) {
+                on_write(cf_name, bytes);
+            }
+        }
+    }
+
+    fn cf_name(&self, cf: &ColumnFamily) -> Option<&str> {
+        self.0
+            .cf_names
+            .iter()
+            .find(|cf_name| {
+                self.handle(cf_name)
+                    .is_some_and(|handle| std::ptr::eq(handle, cf))
+            })
+            .map(String::as_str)
+    }
+
+    /// The latest sequence number seen by this handle.
+    ///
+    /// This fails with [`Error::Unsupported`] for pessimistic transaction databases, since RocksDB's
+    /// Rust bindings do not expose it for them.
+    pub fn latest_sequence_number(&self) -> Result<u64, Error> {
+        match &self.0.inner {
+            DbInner::ReadOnly(db) => Ok(db.latest_sequence_number()),
+            DbInner::ReadWrite(db) => Ok(db.latest_sequence_number()),
+            DbInner::OptimisticTransaction(db) => Ok(db.latest_sequence_number()),
+            DbInner::PessimisticTransaction(_) => Err(Error::Unsupported),
+        }
+    }
+
     pub fn get<K: AsRef<[u8]>>(
         &self,
         cf: &ColumnFamily,
         key: K,
     ) -> Result<Option<DBPinnableSlice<'_>>, rocksdb::Error> {
-        match self.0.as_ref() {
-            DbInner::ReadOnly(db) => db.get_pinned_cf(cf, key),
-            DbInner::OptimisticTransaction(db) => db.get_pinned_cf(cf, key),
-            DbInner::PessimisticTransaction(db) => db.get_pinned_cf(cf, key),
+        self.get_opt(cf, key, &ReadOptions::default())
+    }
+
+    /// Look up a key using the given read options (for example to avoid filling the block cache).
+    pub fn get_opt<K: AsRef<[u8]>>(
+        &self,
+        cf: &ColumnFamily,
+        key: K,
+        read_options: &ReadOptions,
+    ) -> Result<Option<DBPinnableSlice<'_>>, rocksdb::Error> {
+        match &self.0.inner {
+            DbInner::ReadOnly(db) => db.get_pinned_cf_opt(cf, key, read_options),
+            DbInner::ReadWrite(db) => db.get_pinned_cf_opt(cf, key, read_options),
+            DbInner::OptimisticTransaction(db) => db.get_pinned_cf_opt(cf, key, read_options),
+            DbInner::PessimisticTransaction(db) => db.get_pinned_cf_opt(cf, key, read_options),
+        }
+    }
+
+    /// Check cheaply whether a key may exist, without reading its value.
+    ///
+    /// This may return false positives, but a `false` result means that the key definitely does
+    /// not exist.
+    pub fn key_may_exist<K: AsRef<[u8]>>(&self, cf: &ColumnFamily, key: K) -> bool {
+        match &self.0.inner {
+            DbInner::ReadOnly(db) => db.key_may_exist_cf(cf, key),
+            DbInner::ReadWrite(db) => db.key_may_exist_cf(cf, key),
+            DbInner::OptimisticTransaction(db) => db.key_may_exist_cf(cf, key),
+            // The Rust bindings do not support this check for pessimistic transaction databases.
+            DbInner::PessimisticTransaction(_) => true,
+        }
+    }
+
+    /// Check whether a key exists.
+    pub fn contains_key<K: AsRef<[u8]>>(
+        &self,
+        cf: &ColumnFamily,
+        key: K,
+    ) -> Result<bool, rocksdb::Error> {
+        if self.key_may_exist(cf, &key) {
+            Ok(self.get(cf, key)?.is_some())
+        } else {
+            Ok(false)
         }
     }
 
@@ -75,125 +489,266 @@ impl Db {
         cf: &ColumnFamily,
         keys: I,
     ) -> Result<Vec<Option<Vec<u8>>>, rocksdb::Error> {
-        match self.0.as_ref() {
-            DbInner::ReadOnly(db) => db.multi_get_cf(keys.into_iter().map(|key| (cf, key))),
-            DbInner::OptimisticTransaction(db) => {
-                db.multi_get_cf(keys.into_iter().map(|key| (cf, key)))
-            }
-            DbInner::PessimisticTransaction(db) => {
-                db.multi_get_cf(keys.into_iter().map(|key| (cf, key)))
-            }
+        self.multi_get_opt(cf, keys, &ReadOptions::default())
+    }
+
+    /// Look up multiple keys using the given read options.
+    pub fn multi_get_opt<K: AsRef<[u8]>, I: IntoIterator<Item = K>>(
+        &self,
+        cf: &ColumnFamily,
+        keys: I,
+        read_options: &ReadOptions,
+    ) -> Result<Vec<Option<Vec<u8>>>, rocksdb::Error> {
+        let keys = keys.into_iter().map(|key| (cf, key));
+
+        match &self.0.inner {
+            DbInner::ReadOnly(db) => db.multi_get_cf_opt(keys, read_options),
+            DbInner::ReadWrite(db) => db.multi_get_cf_opt(keys, read_options),
+            DbInner::OptimisticTransaction(db) => db.multi_get_cf_opt(keys, read_options),
+            DbInner::PessimisticTransaction(db) => db.multi_get_cf_opt(keys, read_options),
         }
         .into_iter()
         .collect()
     }
 
+    /// Look up multiple keys, decoding each value that is present with the given Bincode
+    /// configuration.
+    ///
+    /// The call fails if any value cannot be decoded.
+    pub fn multi_get_typed<
+        K: AsRef<[u8]>,
+        I: IntoIterator<Item = K>,
+        V: DeserializeOwned,
+        C: bincode::config::Config,
+    >(
+        &self,
+        cf: &ColumnFamily,
+        keys: I,
+        bincode_config: C,
+    ) -> Result<Vec<Option<V>>, mapper::Error> {
+        let keys = keys.into_iter().collect::<Vec<_>>();
+        let values = self.multi_get(cf, &keys)?;
+
+        keys.iter()
+            .zip(values)
+            .map(|(key, value)| {
+                value
+                    .map(|value| {
+                        bincode::serde::decode_from_slice(&value, bincode_config)
+                            .map(|(value, _)| value)
+                            .map_err(|source| mapper::Error::EntryDecoding {
+                                key: key.as_ref().into(),
+                                source,
+                            })
+                    })
+                    .transpose()
+            })
+            .collect()
+    }
+
+    /// Look up multiple keys, fetching each distinct key only once.
+    ///
+    /// The results are aligned with the input keys, including any repeated keys.
+    pub fn multi_get_dedup<K: AsRef<[u8]>, I: IntoIterator<Item = K>>(
+        &self,
+        cf: &ColumnFamily,
+        keys: I,
+    ) -> Result<Vec<Option<Vec<u8>>>, rocksdb::Error> {
+        let keys = keys.into_iter().collect::<Vec<_>>();
+        let mut indices = HashMap::new();
+        let mut unique_keys = vec![];
+
+        let positions = keys
+            .iter()
+            .map(|key| {
+                *indices.entry(key.as_ref()).or_insert_with(|| {
+                    unique_keys.push(key.as_ref());
+                    unique_keys.len() - 1
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let values = self.multi_get(cf, unique_keys)?;
+
+        Ok(positions
+            .into_iter()
+            .map(|index| values[index].clone())
+            .collect())
+    }
+
     pub fn put<K: AsRef<[u8]>, V: AsRef<[u8]>>(
         &self,
         cf: &ColumnFamily,
         key: K,
         value: V,
     ) -> Result<(), rocksdb::Error> {
-        match self.0.as_ref() {
-            DbInner::ReadOnly(db) => db.put_cf(cf, key, value),
-            DbInner::OptimisticTransaction(db) => db.put_cf(cf, key, value),
-            DbInner::PessimisticTransaction(db) => db.put_cf(cf, key, value),
-        }
+        self.put_opt(cf, key, value, &WriteOptions::default())
     }
 
-    pub fn merge<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+    /// Write a single value outside of a transaction using the given write options.
+    pub fn put_opt<K: AsRef<[u8]>, V: AsRef<[u8]>>(
         &self,
         cf: &ColumnFamily,
         key: K,
         value: V,
+        write_options: &WriteOptions,
     ) -> Result<(), rocksdb::Error> {
-        match self.0.as_ref() {
-            DbInner::ReadOnly(db) => db.merge_cf(cf, key, value),
-            DbInner::OptimisticTransaction(db) => db.merge_cf(cf, key, value),
-            DbInner::PessimisticTransaction(db) => db.merge_cf(cf, key, value),
-        }
+        let bytes = value.as_ref().len();
+
+        match &self.0.inner {
+            DbInner::ReadOnly(db) => db.put_cf_opt(cf, key, value, write_options),
+            DbInner::ReadWrite(db) => db.put_cf_opt(cf, key, value, write_options),
+            DbInner::OptimisticTransaction(db) => db.put_cf_opt(cf, key, value, write_options),
+            DbInner::PessimisticTransaction(db) => db.put_cf_opt(cf, key, value, write_options),
+        }?;
+
+        self.record_write(cf, bytes);
+
+        Ok(())
     }
 
-    pub fn iterator(
+    /// Write a value under a typed key.
+    ///
+    /// The value is encoded with the given Bincode configuration, while the key is always encoded
+    /// with fixed-width big-endian integers so that keys sort consistently.
+    pub fn put_typed<
+        K: serde::ser::Serialize,
+        V: serde::ser::Serialize,
+        C: bincode::config::Config,
+    >(
         &self,
         cf: &ColumnFamily,
-        mode: IteratorMode,
-    ) -> impl Iterator<Item = Result<KeyValuePair, rocksdb::Error>> + use<'_> {
-        let iterator: Box<dyn Iterator<Item = Result<KeyValuePair, rocksdb::Error>>> =
-            match self.0.as_ref() {
-                DbInner::ReadOnly(db) => Box::new(db.iterator_cf(cf, mode)),
-                DbInner::OptimisticTransaction(db) => Box::new(db.iterator_cf(cf, mode)),
-                DbInner::PessimisticTransaction(db) => Box::new(db.iterator_cf(cf, mode)),
-            };
+        key: &K,
+        value: &V,
+        bincode_config: C,
+    ) -> Result<(), mapper::Error> {
+        let key = bincode::serde::encode_to_vec(key, KEY_BINCODE_CONFIG)?;
+        let value = bincode::serde::encode_to_vec(value, bincode_config)?;
 
-        iterator
+        Ok(self.put(cf, key, value)?)
     }
 
-    pub fn close(self) {
-        std::mem::drop(self)
-    }
-}
+    /// Read a value written with [`Db::put_typed`].
+    pub fn get_typed<K: serde::ser::Serialize, V: DeserializeOwned, C: bincode::config::Config>(
+        &self,
+        cf: &ColumnFamily,
+        key: &K,
+        bincode_config: C,
+    ) -> Result<Option<V>, mapper::Error> {
+        let key = bincode::serde::encode_to_vec(key, KEY_BINCODE_CONFIG)?;
 
-/// Simple abstraction over transaction type (optimistic or pessmistic).
-pub enum Transaction<'a> {
-    Optimistic(rocksdb::Transaction<'a, OptimisticTransactionDB>),
-    Pessimistic(rocksdb::Transaction<'a, TransactionDB>),
-}
+        self.get(cf, &key)?
+            .map(|value| {
+                bincode::serde::decode_from_slice(&value, bincode_config)
+                    .map(|(value, _)| value)
+                    .map_err(|source| mapper::Error::EntryDecoding {
+                        key: key.into(),
+                        source,
+                    })
+            })
+            .transpose()
+    }
 
-impl<'a> Transaction<'a> {
-    pub fn commit(self) -> Result<(), rocksdb::Error> {
-        match self {
-            Self::Optimistic(tx) => tx.commit(),
-            Self::Pessimistic(tx) => tx.commit(),
-        }
+    /// Delete a key.
+    ///
+    /// This fails for read-only databases.
+    pub fn delete<K: AsRef<[u8]>>(&self, cf: &ColumnFamily, key: K) -> Result<(), rocksdb::Error> {
+        self.delete_opt(cf, key, &WriteOptions::default())
     }
 
-    pub fn get<K: AsRef<[u8]>>(
+    /// Delete a single value outside of a transaction using the given write options.
+    pub fn delete_opt<K: AsRef<[u8]>>(
         &self,
         cf: &ColumnFamily,
         key: K,
-    ) -> Result<Option<DBPinnableSlice<'_>>, rocksdb::Error> {
-        match self {
-            Self::Optimistic(tx) => tx.get_pinned_cf(cf, key),
-            Self::Pessimistic(tx) => tx.get_pinned_cf(cf, key),
+        write_options: &WriteOptions,
+    ) -> Result<(), rocksdb::Error> {
+        match &self.0.inner {
+            DbInner::ReadOnly(db) => db.delete_cf_opt(cf, key, write_options),
+            DbInner::ReadWrite(db) => db.delete_cf_opt(cf, key, write_options),
+            DbInner::OptimisticTransaction(db) => db.delete_cf_opt(cf, key, write_options),
+            DbInner::PessimisticTransaction(db) => db.delete_cf_opt(cf, key, write_options),
         }
     }
 
-    pub fn multi_get<K: AsRef<[u8]>, I: IntoIterator<Item = K>>(
-        &self,
-        cf: &ColumnFamily,
-        keys: I,
-    ) -> Result<Vec<Option<Vec<u8>>>, rocksdb::Error> {
-        match self {
-            Self::Optimistic(tx) => tx.multi_get_cf(keys.into_iter().map(|key| (cf, key))),
-            Self::Pessimistic(tx) => tx.multi_get_cf(keys.into_iter().map(|key| (cf, key))),
+    /// Build a write batch with the given function and apply it atomically.
+    ///
+    /// This fails for read-only databases. Writes applied this way are not reported to the on-write
+    /// callback.
+    pub fn write_batch<F: FnOnce(&mut WriteBatch)>(&self, f: F) -> Result<(), rocksdb::Error> {
+        let mut batch = WriteBatch::default();
+        f(&mut batch);
+
+        match &self.0.inner {
+            DbInner::ReadOnly(db) => db.write(batch),
+            DbInner::ReadWrite(db) => db.write(batch),
+            DbInner::OptimisticTransaction(db) => {
+                db.write(WriteBatchWithTransaction::from_data(batch.data()))
+            }
+            DbInner::PessimisticTransaction(db) => {
+                db.write(WriteBatchWithTransaction::from_data(batch.data()))
+            }
         }
-        .into_iter()
-        .collect()
     }
 
-    pub fn put<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+    /// Record that a merge operator is configured for the named column family.
+    ///
+    /// Merges are rejected on column families that have not been registered here.
+    pub fn register_merge_operator(&self, cf_name: &str) {
+        if let Some(cf) = self.handle(cf_name) {
+            self.0
+                .merge_operator_cfs
+                .write()
+                .unwrap()
+                .insert(cf_key(cf));
+        }
+    }
+
+    fn has_merge_operator(&self, cf: &ColumnFamily) -> bool {
+        has_merge_operator(&self.0.merge_operator_cfs, cf)
+    }
+
+    fn wrap_transaction<'a>(&'a self, inner: TransactionInner<'a>) -> Transaction<'a> {
+        Transaction {
+            inner,
+            merge_operator_cfs: &self.0.merge_operator_cfs,
+        }
+    }
+
+    /// Merge a single value outside of a transaction.
+    ///
+    /// This fails with [`Error::NoMergeOperator`] if no merge operator has been registered for the
+    /// column family. Because of this check, it returns the crate's [`Error`] (wrapping RocksDB
+    /// errors in [`Error::Db`]) instead of [`rocksdb::Error`] as in earlier versions, and
+    /// [`Transaction::merge`] has changed in the same way.
+    pub fn merge<K: AsRef<[u8]>, V: AsRef<[u8]>>(
         &self,
         cf: &ColumnFamily,
         key: K,
         value: V,
-    ) -> Result<(), rocksdb::Error> {
-        match self {
-            Self::Optimistic(tx) => tx.put_cf(cf, key, value),
-            Self::Pessimistic(tx) => tx.put_cf(cf, key, value),
-        }
+    ) -> Result<(), Error> {
+        self.merge_opt(cf, key, value, &WriteOptions::default())
     }
 
-    pub fn merge<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+    /// Merge a single value outside of a transaction using the given write options.
+    pub fn merge_opt<K: AsRef<[u8]>, V: AsRef<[u8]>>(
         &self,
         cf: &ColumnFamily,
         key: K,
         value: V,
-    ) -> Result<(), rocksdb::Error> {
-        match self {
-            Self::Optimistic(tx) => tx.merge_cf(cf, key, value),
-            Self::Pessimistic(tx) => tx.merge_cf(cf, key, value),
+        write_options: &WriteOptions,
+    ) -> Result<(), Error> {
+        if !self.has_merge_operator(cf) {
+            return Err(Error::NoMergeOperator);
+        }
+
+        match &self.0.inner {
+            DbInner::ReadOnly(db) => db.merge_cf_opt(cf, key, value, write_options),
+            DbInner::ReadWrite(db) => db.merge_cf_opt(cf, key, value, write_options),
+            DbInner::OptimisticTransaction(db) => db.merge_cf_opt(cf, key, value, write_options),
+            DbInner::PessimisticTransaction(db) => db.merge_cf_opt(cf, key, value, write_options),
         }
+        .map_err(Error::from)
     }
 
     pub fn iterator(
@@ -201,11 +756,1757 @@ impl<'a> Transaction<'a> {
         cf: &ColumnFamily,
         mode: IteratorMode,
     ) -> impl Iterator<Item = Result<KeyValuePair, rocksdb::Error>> + use<'_> {
-        let iterator: Box<dyn Iterator<Item = Result<KeyValuePair, rocksdb::Error>>> = match self {
-            Self::Optimistic(tx) => Box::new(tx.iterator_cf(cf, mode)),
-            Self::Pessimistic(tx) => Box::new(tx.iterator_cf(cf, mode)),
+        let iterator: Box<dyn Iterator<Item = Result<KeyValuePair, rocksdb::Error>>> =
+            match &self.0.inner {
+                DbInner::ReadOnly(db) => Box::new(db.iterator_cf(cf, mode)),
+                DbInner::ReadWrite(db) => Box::new(db.iterator_cf(cf, mode)),
+                DbInner::OptimisticTransaction(db) => Box::new(db.iterator_cf(cf, mode)),
+                DbInner::PessimisticTransaction(db) => Box::new(db.iterator_cf(cf, mode)),
+            };
+
+        iterator
+    }
+
+    /// Read up to `limit` entries in key order, starting after the given cursor key (or from the
+    /// start of the column family).
+    ///
+    /// The returned cursor is the last key of the page, and is `None` if there are no more entries.
+    /// A limit of zero fails with [`Error::ZeroPageLimit`], since no cursor could mark the position.
+    pub fn page(
+        &self,
+        cf: &ColumnFamily,
+        after: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Page, Error> {
+        if limit == 0 {
+            return Err(Error::ZeroPageLimit);
+        }
+
+        let mode = after.map_or(IteratorMode::Start, |after| {
+            IteratorMode::From(after, Direction::Forward)
+        });
+
+        let mut entries = self
+            .iterator(cf, mode)
+            .filter(|result| {
+                result
+                    .as_ref()
+                    .map_or(true, |(key, _)| Some(key.as_ref()) != after)
+            })
+            .take(limit.saturating_add(1))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let cursor = if entries.len() > limit {
+            entries.truncate(limit);
+            entries.last().map(|(key, _)| key.clone())
+        } else {
+            None
+        };
+
+        Ok((entries, cursor))
+    }
+
+    /// Iterate over the keys in a column family, without copying values.
+    pub fn key_iterator(
+        &self,
+        cf: &ColumnFamily,
+        mode: IteratorMode,
+    ) -> impl Iterator<Item = Result<Box<[u8]>, rocksdb::Error>> + use<'_> {
+        let iterator: Box<dyn Iterator<Item = Result<Box<[u8]>, rocksdb::Error>>> = match &self
+            .0
+            .inner
+        {
+            DbInner::ReadOnly(db) => Box::new(raw_keys(db.raw_iterator_cf(cf), mode)),
+            DbInner::ReadWrite(db) => Box::new(raw_keys(db.raw_iterator_cf(cf), mode)),
+            DbInner::OptimisticTransaction(db) => Box::new(raw_keys(db.raw_iterator_cf(cf), mode)),
+            DbInner::PessimisticTransaction(db) => Box::new(raw_keys(db.raw_iterator_cf(cf), mode)),
         };
 
         iterator
     }
+
+    /// Iterate over a column family starting from the given key, or from the nearest key after it
+    /// (or before it when iterating in reverse).
+    pub fn iterator_from<K: AsRef<[u8]>>(
+        &self,
+        cf: &ColumnFamily,
+        key: K,
+        forward: bool,
+    ) -> impl Iterator<Item = Result<KeyValuePair, rocksdb::Error>> + use<'_, K> {
+        let direction = if forward {
+            Direction::Forward
+        } else {
+            Direction::Reverse
+        };
+
+        self.iterator(cf, IteratorMode::From(key.as_ref(), direction))
+    }
+
+    /// Iterate over the entries in a column family whose keys start with the given prefix.
+    ///
+    /// Iteration stops at the first key without the prefix, even if no prefix extractor is
+    /// configured for the column family.
+    pub fn prefix_iterator<P: AsRef<[u8]>>(
+        &self,
+        cf: &ColumnFamily,
+        prefix: P,
+    ) -> impl Iterator<Item = Result<KeyValuePair, rocksdb::Error>> + use<'_, P> {
+        let prefix = prefix.as_ref().to_vec();
+
+        let iterator: Box<dyn Iterator<Item = Result<KeyValuePair, rocksdb::Error>>> =
+            match &self.0.inner {
+                DbInner::ReadOnly(db) => Box::new(db.prefix_iterator_cf(cf, &prefix)),
+                DbInner::ReadWrite(db) => Box::new(db.prefix_iterator_cf(cf, &prefix)),
+                DbInner::OptimisticTransaction(db) => Box::new(db.prefix_iterator_cf(cf, &prefix)),
+                DbInner::PessimisticTransaction(db) => Box::new(db.prefix_iterator_cf(cf, &prefix)),
+            };
+
+        iterator.take_while(move |result| {
+            result
+                .as_ref()
+                .map_or(true, |(key, _)| key.starts_with(&prefix))
+        })
+    }
+
+    /// Iterate over a column family with the given read-ahead size, which can reduce the number of
+    /// I/O operations for large sequential scans.
+    pub fn iterator_with_readahead(
+        &self,
+        cf: &ColumnFamily,
+        mode: IteratorMode,
+        readahead_size: usize,
+    ) -> impl Iterator<Item = Result<KeyValuePair, rocksdb::Error>> + use<'_> {
+        let mut read_options = ReadOptions::default();
+        read_options.set_readahead_size(readahead_size);
+
+        let iterator: Box<dyn Iterator<Item = Result<KeyValuePair, rocksdb::Error>>> =
+            match &self.0.inner {
+                DbInner::ReadOnly(db) => Box::new(db.iterator_cf_opt(cf, read_options, mode)),
+                DbInner::ReadWrite(db) => Box::new(db.iterator_cf_opt(cf, read_options, mode)),
+                DbInner::OptimisticTransaction(db) => {
+                    Box::new(db.iterator_cf_opt(cf, read_options, mode))
+                }
+                DbInner::PessimisticTransaction(db) => {
+                    Box::new(db.iterator_cf_opt(cf, read_options, mode))
+                }
+            };
+
+        iterator
+    }
+
+    /// Iterate over the entries in a column family with keys in the half-open range `[start, end)`.
+    pub fn range_iterator<K: AsRef<[u8]>>(
+        &self,
+        cf: &ColumnFamily,
+        start: K,
+        end: K,
+    ) -> impl Iterator<Item = Result<KeyValuePair, rocksdb::Error>> + use<'_, K> {
+        let mut read_options = ReadOptions::default();
+        read_options.set_iterate_lower_bound(start.as_ref());
+        read_options.set_iterate_upper_bound(end.as_ref());
+
+        let mode = IteratorMode::Start;
+
+        let iterator: Box<dyn Iterator<Item = Result<KeyValuePair, rocksdb::Error>>> =
+            match &self.0.inner {
+                DbInner::ReadOnly(db) => Box::new(db.iterator_cf_opt(cf, read_options, mode)),
+                DbInner::ReadWrite(db) => Box::new(db.iterator_cf_opt(cf, read_options, mode)),
+                DbInner::OptimisticTransaction(db) => {
+                    Box::new(db.iterator_cf_opt(cf, read_options, mode))
+                }
+                DbInner::PessimisticTransaction(db) => {
+                    Box::new(db.iterator_cf_opt(cf, read_options, mode))
+                }
+            };
+
+        iterator
+    }
+
+    /// Iterate over a column family, decoding keys and values with the given Bincode configuration.
+    ///
+    /// A key or value that cannot be decoded is returned as an error item carrying the raw key, and
+    /// iteration continues with the next entry.
+    pub fn typed_iterator<K: DeserializeOwned, V: DeserializeOwned, C: bincode::config::Config>(
+        &self,
+        cf: &ColumnFamily,
+        mode: IteratorMode,
+        bincode_config: C,
+    ) -> impl Iterator<Item = Result<(K, V), mapper::Error>> + use<'_, K, V, C> {
+        self.decoding_iterator(cf, mode, move |key, value| {
+            decode_entry(key, value, bincode_config)
+        })
+    }
+
+    /// Iterate over a column family, decoding entries with the given function.
+    pub(crate) fn decoding_iterator<K, V, F>(
+        &self,
+        cf: &ColumnFamily,
+        mode: IteratorMode,
+        decode: F,
+    ) -> impl Iterator<Item = Result<(K, V), mapper::Error>> + use<'_, K, V, F>
+    where
+        F: Fn(&[u8], &[u8]) -> Result<(K, V), bincode::error::DecodeError>,
+    {
+        self.iterator(cf, mode).map(move |result| {
+            let (key, value) = result?;
+
+            decode(&key, &value).map_err(|source| mapper::Error::EntryDecoding { key, source })
+        })
+    }
+
+    /// Compact the full key range of a column family.
+    ///
+    /// This fails with [`Error::Unsupported`] for read-only and pessimistic transaction databases.
+    pub fn compact(&self, cf: &ColumnFamily) -> Result<(), Error> {
+        self.compact_range::<&[u8], &[u8]>(cf, None, None)
+    }
+
+    /// Compact the given key range of a column family, where missing bounds are unbounded.
+    ///
+    /// This fails with [`Error::Unsupported`] for read-only and pessimistic transaction databases.
+    pub fn compact_range<S: AsRef<[u8]>, E: AsRef<[u8]>>(
+        &self,
+        cf: &ColumnFamily,
+        start: Option<S>,
+        end: Option<E>,
+    ) -> Result<(), Error> {
+        match &self.0.inner {
+            DbInner::ReadOnly(_) | DbInner::PessimisticTransaction(_) => {
+                return Err(Error::Unsupported)
+            }
+            DbInner::ReadWrite(db) => db.compact_range_cf(cf, start, end),
+            DbInner::OptimisticTransaction(db) => db.compact_range_cf(cf, start, end),
+        }
+
+        Ok(())
+    }
+
+    /// Look up an integer-valued RocksDB property for a column family.
+    ///
+    /// This always returns `None` for databases using pessimistic transactions.
+    pub fn property_int(
+        &self,
+        cf: &ColumnFamily,
+        property: &str,
+    ) -> Result<Option<u64>, rocksdb::Error> {
+        match &self.0.inner {
+            DbInner::ReadOnly(db) => db.property_int_value_cf(cf, property),
+            DbInner::ReadWrite(db) => db.property_int_value_cf(cf, property),
+            DbInner::OptimisticTransaction(db) => db.property_int_value_cf(cf, property),
+            DbInner::PessimisticTransaction(_) => Ok(None),
+        }
+    }
+
+    /// Count the keys in a column family.
+    ///
+    /// This iterates over the entire column family, so it takes time linear in its size.
+    pub fn count(&self, cf: &ColumnFamily) -> Result<u64, rocksdb::Error> {
+        self.iterator(cf, IteratorMode::Start)
+            .try_fold(0, |count, result| result.map(|_| count + 1))
+    }
+
+    /// Estimate the number of keys in a column family without iterating over it.
+    ///
+    /// This always returns `None` for databases using pessimistic transactions.
+    pub fn estimate_count(&self, cf: &ColumnFamily) -> Result<Option<u64>, rocksdb::Error> {
+        self.property_int(cf, "rocksdb.estimate-num-keys")
+    }
+
+    /// Take a snapshot that provides a consistent point-in-time view for reads.
+    pub fn snapshot(&self) -> Snapshot<'_> {
+        match &self.0.inner {
+            DbInner::ReadOnly(db) => Snapshot::Plain(db.snapshot()),
+            DbInner::ReadWrite(db) => Snapshot::Plain(db.snapshot()),
+            DbInner::OptimisticTransaction(db) => Snapshot::Optimistic(db.snapshot()),
+            DbInner::PessimisticTransaction(db) => Snapshot::Pessimistic(db.snapshot()),
+        }
+    }
+
+    /// Create a checkpoint of the database in a directory that does not yet exist.
+    ///
+    /// This is not supported for pessimistic transaction databases.
+    pub fn checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        if path.as_ref().exists() {
+            return Err(Error::CheckpointTargetExists {
+                path: path.as_ref().to_path_buf(),
+            });
+        }
+
+        match &self.0.inner {
+            DbInner::ReadOnly(db) => Checkpoint::new(db)?.create_checkpoint(path),
+            DbInner::ReadWrite(db) => Checkpoint::new(db)?.create_checkpoint(path),
+            DbInner::OptimisticTransaction(db) => Checkpoint::new(db)?.create_checkpoint(path),
+            DbInner::PessimisticTransaction(_) => return Err(Error::Unsupported),
+        }
+        .map_err(Error::from)
+    }
+
+    /// Write any buffered WAL entries to the log file, and optionally sync it to disk.
+    ///
+    /// This is cheaper than flushing memtables, and is only needed when the database was opened
+    /// with `manual_wal_flush` or writes were not synced. It is not supported for read-only or
+    /// pessimistic transaction databases.
+    pub fn flush_wal(&self, sync: bool) -> Result<(), Error> {
+        match &self.0.inner {
+            DbInner::ReadWrite(db) => db.flush_wal(sync),
+            DbInner::OptimisticTransaction(db) => db.flush_wal(sync),
+            DbInner::ReadOnly(_) | DbInner::PessimisticTransaction(_) => {
+                return Err(Error::Unsupported)
+            }
+        }
+        .map_err(Error::from)
+    }
+
+    /// Write any buffered WAL entries to the log file and sync it to disk.
+    pub fn sync_wal(&self) -> Result<(), Error> {
+        self.flush_wal(true)
+    }
+
+    /// Change mutable options (for example `write_buffer_size`) of the named column family without
+    /// reopening the database.
+    ///
+    /// RocksDB rejects options that cannot be changed at runtime. This is not supported for
+    /// pessimistic transaction databases.
+    pub fn set_cf_options(&self, cf_name: &str, opts: &[(&str, &str)]) -> Result<(), Error> {
+        let cf = self
+            .handle(cf_name)
+            .ok_or_else(|| Error::UnknownColumnFamily(cf_name.to_string()))?;
+
+        match &self.0.inner {
+            DbInner::ReadOnly(db) => db.set_options_cf(cf, opts),
+            DbInner::ReadWrite(db) => db.set_options_cf(cf, opts),
+            DbInner::OptimisticTransaction(db) => db.set_options_cf(cf, opts),
+            DbInner::PessimisticTransaction(_) => return Err(Error::Unsupported),
+        }
+        .map_err(Error::from)
+    }
+
+    /// Ingest externally created SST files into a column family.
+    ///
+    /// This is not supported for read-only or pessimistic transaction databases.
+    pub fn ingest_external_file(&self, cf: &ColumnFamily, paths: &[PathBuf]) -> Result<(), Error> {
+        self.ingest_external_file_opt(cf, paths, &IngestExternalFileOptions::default())
+    }
+
+    /// Ingest externally created SST files into a column family using the given options.
+    ///
+    /// This is not supported for read-only or pessimistic transaction databases.
+    pub fn ingest_external_file_opt(
+        &self,
+        cf: &ColumnFamily,
+        paths: &[PathBuf],
+        ingest_options: &IngestExternalFileOptions,
+    ) -> Result<(), Error> {
+        match &self.0.inner {
+            DbInner::ReadWrite(db) => {
+                db.ingest_external_file_cf_opts(cf, ingest_options, paths.to_vec())
+            }
+            DbInner::OptimisticTransaction(db) => {
+                db.ingest_external_file_cf_opts(cf, ingest_options, paths.to_vec())
+            }
+            DbInner::ReadOnly(_) | DbInner::PessimisticTransaction(_) => {
+                return Err(Error::Unsupported)
+            }
+        }
+        .map_err(Error::from)
+    }
+
+    /// Drop this wrapper.
+    ///
+    /// The underlying database is only closed when the last clone is dropped, so this is a no-op
+    /// if other clones are outstanding. Unflushed memtables are only flushed on close if
+    /// [`Db::set_flush_on_drop`] has been enabled (otherwise RocksDB replays the write-ahead log
+    /// on the next open).
+    pub fn close(self) {
+        std::mem::drop(self)
+    }
+
+    /// Flush all known column families and then drop this wrapper.
+    ///
+    /// As with [`Db::close`], the underlying database stays open while other clones are
+    /// outstanding, but any writes made before this call are flushed regardless.
+    pub fn close_and_flush(self) -> Result<(), Error> {
+        self.flush()?;
+        self.close();
+
+        Ok(())
+    }
+}
+
+/// Position a raw iterator according to the iterator mode and yield copies of its keys.
+fn raw_keys<'a, D: DBAccess>(
+    mut iterator: DBRawIteratorWithThreadMode<'a, D>,
+    mode: IteratorMode,
+) -> impl Iterator<Item = Result<Box<[u8]>, rocksdb::Error>> + use<'a, D> {
+    let forward = match mode {
+        IteratorMode::Start => {
+            iterator.seek_to_first();
+            true
+        }
+        IteratorMode::End => {
+            iterator.seek_to_last();
+            false
+        }
+        IteratorMode::From(key, Direction::Forward) => {
+            iterator.seek(key);
+            true
+        }
+        IteratorMode::From(key, Direction::Reverse) => {
+            iterator.seek_for_prev(key);
+            false
+        }
+    };
+    let mut done = false;
+
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+
+        match iterator.key() {
+            Some(key) => {
+                let key = key.into();
+
+                if forward {
+                    iterator.next();
+                } else {
+                    iterator.prev();
+                }
+
+                Some(Ok(key))
+            }
+            None => {
+                done = true;
+
+                iterator.status().err().map(Err)
+            }
+        }
+    })
+}
+
+fn decode_entry<K: DeserializeOwned, V: DeserializeOwned, C: bincode::config::Config>(
+    key: &[u8],
+    value: &[u8],
+    bincode_config: C,
+) -> Result<(K, V), bincode::error::DecodeError> {
+    let (key, _) = bincode::serde::decode_from_slice(key, bincode_config)?;
+    let (value, _) = bincode::serde::decode_from_slice(value, bincode_config)?;
+
+    Ok((key, value))
+}
+
+/// Owned column family handle that holds a clone of the database wrapper it belongs to.
+#[derive(Clone, Debug)]
+pub struct BoundColumnFamily {
+    db: Db,
+    name: String,
+}
+
+impl BoundColumnFamily {
+    pub fn db(&self) -> &Db {
+        &self.db
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn handle(&self) -> &ColumnFamily {
+        // Safe because we checked that the column family exists on creation, and column families
+        // are never dropped while the database is open.
+        self.db.handle(&self.name).unwrap()
+    }
+}
+
+/// Writes pending for a plain database, together with any savepoints.
+#[derive(Default)]
+pub struct PendingBatch {
+    batch: WriteBatch,
+    savepoints: Vec<Vec<u8>>,
+    write_options: WriteOptions,
+}
+
+/// Simple abstraction over transaction type (optimistic, pessmistic, or write batch).
+///
+/// Reads in a write batch go directly to the database and do not see uncommitted writes.
+pub struct Transaction<'a> {
+    inner: TransactionInner<'a>,
+    merge_operator_cfs: &'a RwLock<HashSet<usize>>,
+}
+
+enum TransactionInner<'a> {
+    Optimistic(rocksdb::Transaction<'a, OptimisticTransactionDB>),
+    Pessimistic(rocksdb::Transaction<'a, TransactionDB>),
+    Batch(&'a DB, RefCell<PendingBatch>),
+}
+
+impl<'a> Transaction<'a> {
+    pub fn commit(self) -> Result<(), rocksdb::Error> {
+        match self.inner {
+            TransactionInner::Optimistic(tx) => tx.commit(),
+            TransactionInner::Pessimistic(tx) => tx.commit(),
+            TransactionInner::Batch(db, batch) => {
+                let pending = batch.into_inner();
+
+                db.write_opt(pending.batch, &pending.write_options)
+            }
+        }
+    }
+
+    /// Name the transaction, which is required before it can be prepared.
+    ///
+    /// This is only supported for pessimistic transactions.
+    pub fn set_name(&self, name: &[u8]) -> Result<(), Error> {
+        match &self.inner {
+            TransactionInner::Pessimistic(tx) => tx.set_name(name).map_err(Error::from),
+            TransactionInner::Optimistic(_) | TransactionInner::Batch(_, _) => {
+                Err(Error::Unsupported)
+            }
+        }
+    }
+
+    /// Prepare a named transaction for a two-phase commit, persisting its writes to the WAL.
+    ///
+    /// The prepared transaction is completed with [`Transaction::commit`] or discarded with
+    /// [`Transaction::rollback`]. This is only supported for pessimistic transactions.
+    pub fn prepare(&self) -> Result<(), Error> {
+        match &self.inner {
+            TransactionInner::Pessimistic(tx) => tx.prepare().map_err(Error::from),
+            TransactionInner::Optimistic(_) | TransactionInner::Batch(_, _) => {
+                Err(Error::Unsupported)
+            }
+        }
+    }
+
+    /// Discard all writes made in this transaction.
+    pub fn rollback(self) -> Result<(), rocksdb::Error> {
+        match self.inner {
+            TransactionInner::Optimistic(tx) => tx.rollback(),
+            TransactionInner::Pessimistic(tx) => tx.rollback(),
+            TransactionInner::Batch(_, _) => Ok(()),
+        }
+    }
+
+    /// Record the current state of the transaction, which can be restored with
+    /// [`Transaction::rollback_to_savepoint`].
+    pub fn set_savepoint(&self) {
+        match &self.inner {
+            TransactionInner::Optimistic(tx) => tx.set_savepoint(),
+            TransactionInner::Pessimistic(tx) => tx.set_savepoint(),
+            TransactionInner::Batch(_, batch) => {
+                let mut batch = batch.borrow_mut();
+                let data = batch.batch.data().to_vec();
+
+                batch.savepoints.push(data);
+            }
+        }
+    }
+
+    /// Discard all writes made since the most recent savepoint, and remove that savepoint.
+    ///
+    /// This fails if there is no savepoint, except for write batches, where it has no effect.
+    pub fn rollback_to_savepoint(&self) -> Result<(), rocksdb::Error> {
+        match &self.inner {
+            TransactionInner::Optimistic(tx) => tx.rollback_to_savepoint(),
+            TransactionInner::Pessimistic(tx) => tx.rollback_to_savepoint(),
+            TransactionInner::Batch(_, batch) => {
+                let mut batch = batch.borrow_mut();
+
+                if let Some(data) = batch.savepoints.pop() {
+                    batch.batch = WriteBatch::from_data(&data);
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    pub fn get<K: AsRef<[u8]>>(
+        &self,
+        cf: &ColumnFamily,
+        key: K,
+    ) -> Result<Option<DBPinnableSlice<'_>>, rocksdb::Error> {
+        match &self.inner {
+            TransactionInner::Optimistic(tx) => tx.get_pinned_cf(cf, key),
+            TransactionInner::Pessimistic(tx) => tx.get_pinned_cf(cf, key),
+            TransactionInner::Batch(db, _) => db.get_pinned_cf(cf, key),
+        }
+    }
+
+    /// Read a value and register it with the transaction, so that a conflicting write causes the
+    /// commit to fail (for optimistic transactions) or is blocked (for pessimistic transactions).
+    ///
+    /// Write batch transactions do not track reads.
+    pub fn get_for_update<K: AsRef<[u8]>>(
+        &self,
+        cf: &ColumnFamily,
+        key: K,
+    ) -> Result<Option<DBPinnableSlice<'_>>, rocksdb::Error> {
+        match &self.inner {
+            TransactionInner::Optimistic(tx) => tx.get_pinned_for_update_cf(cf, key, true),
+            TransactionInner::Pessimistic(tx) => tx.get_pinned_for_update_cf(cf, key, true),
+            TransactionInner::Batch(db, _) => db.get_pinned_cf(cf, key),
+        }
+    }
+
+    pub fn multi_get<K: AsRef<[u8]>, I: IntoIterator<Item = K>>(
+        &self,
+        cf: &ColumnFamily,
+        keys: I,
+    ) -> Result<Vec<Option<Vec<u8>>>, rocksdb::Error> {
+        match &self.inner {
+            TransactionInner::Optimistic(tx) => {
+                tx.multi_get_cf(keys.into_iter().map(|key| (cf, key)))
+            }
+            TransactionInner::Pessimistic(tx) => {
+                tx.multi_get_cf(keys.into_iter().map(|key| (cf, key)))
+            }
+            TransactionInner::Batch(db, _) => {
+                db.multi_get_cf(keys.into_iter().map(|key| (cf, key)))
+            }
+        }
+        .into_iter()
+        .collect()
+    }
+
+    pub fn put<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+        &self,
+        cf: &ColumnFamily,
+        key: K,
+        value: V,
+    ) -> Result<(), rocksdb::Error> {
+        match &self.inner {
+            TransactionInner::Optimistic(tx) => tx.put_cf(cf, key, value),
+            TransactionInner::Pessimistic(tx) => tx.put_cf(cf, key, value),
+            TransactionInner::Batch(_, batch) => {
+                batch.borrow_mut().batch.put_cf(cf, key, value);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn delete<K: AsRef<[u8]>>(&self, cf: &ColumnFamily, key: K) -> Result<(), rocksdb::Error> {
+        match &self.inner {
+            TransactionInner::Optimistic(tx) => tx.delete_cf(cf, key),
+            TransactionInner::Pessimistic(tx) => tx.delete_cf(cf, key),
+            TransactionInner::Batch(_, batch) => {
+                batch.borrow_mut().batch.delete_cf(cf, key);
+                Ok(())
+            }
+        }
+    }
+
+    /// Merge a value in this transaction.
+    ///
+    /// Like [`Db::merge`], this fails with [`Error::NoMergeOperator`] if no merge operator has been
+    /// registered for the column family.
+    pub fn merge<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+        &self,
+        cf: &ColumnFamily,
+        key: K,
+        value: V,
+    ) -> Result<(), Error> {
+        if !has_merge_operator(self.merge_operator_cfs, cf) {
+            return Err(Error::NoMergeOperator);
+        }
+
+        match &self.inner {
+            TransactionInner::Optimistic(tx) => tx.merge_cf(cf, key, value),
+            TransactionInner::Pessimistic(tx) => tx.merge_cf(cf, key, value),
+            TransactionInner::Batch(_, batch) => {
+                batch.borrow_mut().batch.merge_cf(cf, key, value);
+                Ok(())
+            }
+        }
+        .map_err(Error::from)
+    }
+
+    pub fn iterator(
+        &self,
+        cf: &ColumnFamily,
+        mode: IteratorMode,
+    ) -> impl Iterator<Item = Result<KeyValuePair, rocksdb::Error>> + use<'_> {
+        let iterator: Box<dyn Iterator<Item = Result<KeyValuePair, rocksdb::Error>>> =
+            match &self.inner {
+                TransactionInner::Optimistic(tx) => Box::new(tx.iterator_cf(cf, mode)),
+                TransactionInner::Pessimistic(tx) => Box::new(tx.iterator_cf(cf, mode)),
+                TransactionInner::Batch(db, _) => Box::new(db.iterator_cf(cf, mode)),
+            };
+
+        iterator
+    }
+}
+
+/// Simple abstraction over snapshots of the supported database types.
+pub enum Snapshot<'a> {
+    Plain(SnapshotWithThreadMode<'a, DB>),
+    Optimistic(SnapshotWithThreadMode<'a, OptimisticTransactionDB>),
+    Pessimistic(SnapshotWithThreadMode<'a, TransactionDB>),
+}
+
+impl<'a> Snapshot<'a> {
+    pub fn get<K: AsRef<[u8]>>(
+        &self,
+        cf: &ColumnFamily,
+        key: K,
+    ) -> Result<Option<DBPinnableSlice<'_>>, rocksdb::Error> {
+        match self {
+            Self::Plain(snapshot) => snapshot.get_pinned_cf(cf, key),
+            Self::Optimistic(snapshot) => snapshot.get_pinned_cf(cf, key),
+            Self::Pessimistic(snapshot) => snapshot.get_pinned_cf(cf, key),
+        }
+    }
+
+    pub fn multi_get<K: AsRef<[u8]>, I: IntoIterator<Item = K>>(
+        &self,
+        cf: &ColumnFamily,
+        keys: I,
+    ) -> Result<Vec<Option<Vec<u8>>>, rocksdb::Error> {
+        match self {
+            Self::Plain(snapshot) => snapshot.multi_get_cf(keys.into_iter().map(|key| (cf, key))),
+            Self::Optimistic(snapshot) => {
+                snapshot.multi_get_cf(keys.into_iter().map(|key| (cf, key)))
+            }
+            Self::Pessimistic(snapshot) => {
+                snapshot.multi_get_cf(keys.into_iter().map(|key| (cf, key)))
+            }
+        }
+        .into_iter()
+        .collect()
+    }
+
+    pub fn iterator(
+        &self,
+        cf: &ColumnFamily,
+        mode: IteratorMode,
+    ) -> impl Iterator<Item = Result<KeyValuePair, rocksdb::Error>> + use<'_, 'a> {
+        let iterator: Box<dyn Iterator<Item = Result<KeyValuePair, rocksdb::Error>>> = match self {
+            Self::Plain(snapshot) => Box::new(snapshot.iterator_cf(cf, mode)),
+            Self::Optimistic(snapshot) => Box::new(snapshot.iterator_cf(cf, mode)),
+            Self::Pessimistic(snapshot) => Box::new(snapshot.iterator_cf(cf, mode)),
+        };
+
+        iterator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    fn open_test_db(cf_names: &[&str]) -> (tempfile::TempDir, super::Db) {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+
+        let db = rocksdb::OptimisticTransactionDB::open_cf_descriptors(
+            &options,
+            &test_db_dir,
+            cf_names.iter().map(|cf_name| {
+                rocksdb::ColumnFamilyDescriptor::new(*cf_name, rocksdb::Options::default())
+            }),
+        )
+        .unwrap();
+
+        (test_db_dir, db.into())
+    }
+
+    #[test]
+    fn cf_arc() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+
+        assert!(db.cf_arc("missing").is_none());
+
+        // The bound handle can outlive the original wrapper.
+        let bound = db.cf_arc("test").unwrap();
+        drop(db);
+
+        for i in 0..10u64 {
+            bound
+                .db()
+                .put(bound.handle(), i.to_be_bytes(), i.to_le_bytes())
+                .unwrap();
+        }
+
+        assert_eq!(bound.name(), "test");
+        assert_eq!(
+            bound
+                .db()
+                .get(bound.handle(), 3u64.to_be_bytes())
+                .unwrap()
+                .unwrap()
+                .as_ref(),
+            3u64.to_le_bytes()
+        );
+        assert_eq!(
+            bound
+                .db()
+                .iterator(bound.handle(), rocksdb::IteratorMode::Start)
+                .count(),
+            10
+        );
+    }
+
+    #[test]
+    fn set_cf_options() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        db.set_cf_options("test", &[("disable_auto_compactions", "true")])
+            .unwrap();
+
+        for i in 0..100u64 {
+            db.put(cf, i.to_be_bytes(), [0; 64]).unwrap();
+        }
+
+        assert_eq!(db.iterator(cf, rocksdb::IteratorMode::Start).count(), 100);
+        assert!(db
+            .set_cf_options("test", &[("disable_auto_compactions", "false")])
+            .is_ok());
+        assert!(matches!(
+            db.set_cf_options("test", &[("num_levels", "4")]),
+            Err(crate::error::Error::Db(_))
+        ));
+        assert!(matches!(
+            db.set_cf_options("missing", &[("disable_auto_compactions", "true")]),
+            Err(crate::error::Error::UnknownColumnFamily(_))
+        ));
+    }
+
+    #[test]
+    fn ingest_external_file() {
+        let (test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+        let sst_path = test_db_dir.path().join("external.sst");
+
+        let sst_options = rocksdb::Options::default();
+        let mut writer = rocksdb::SstFileWriter::create(&sst_options);
+        writer.open(&sst_path).unwrap();
+
+        for i in 0..100u64 {
+            writer.put(i.to_be_bytes(), i.to_le_bytes()).unwrap();
+        }
+
+        writer.finish().unwrap();
+
+        db.ingest_external_file(cf, &[sst_path]).unwrap();
+
+        assert_eq!(db.iterator(cf, rocksdb::IteratorMode::Start).count(), 100);
+        assert_eq!(
+            db.get(cf, 42u64.to_be_bytes()).unwrap().unwrap().as_ref(),
+            42u64.to_le_bytes()
+        );
+    }
+
+    #[test]
+    fn iterator_with_readahead() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        for i in 0..1000u64 {
+            db.put(cf, i.to_be_bytes(), [0; 64]).unwrap();
+        }
+
+        let expected = db
+            .iterator(cf, rocksdb::IteratorMode::Start)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let results = db
+            .iterator_with_readahead(cf, rocksdb::IteratorMode::Start, 2 * 1024 * 1024)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(results.len(), 1000);
+        assert_eq!(results, expected);
+    }
+
+    #[test]
+    fn typed_iterator() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+        let bincode_config = bincode::config::standard().with_big_endian();
+
+        for (key, value) in [(1u64, "foo"), (2, "bar"), (3, "baz")] {
+            db.put(
+                cf,
+                bincode::serde::encode_to_vec(key, bincode_config).unwrap(),
+                bincode::serde::encode_to_vec(value, bincode_config).unwrap(),
+            )
+            .unwrap();
+        }
+
+        let start_key = bincode::serde::encode_to_vec(2u64, bincode_config).unwrap();
+        let results = db
+            .typed_iterator::<u64, String, _>(
+                cf,
+                rocksdb::IteratorMode::From(&start_key, rocksdb::Direction::Reverse),
+                bincode_config,
+            )
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            results,
+            vec![(2, "bar".to_string()), (1, "foo".to_string())]
+        );
+    }
+
+    #[test]
+    fn typed_iterator_skips_corrupt_entries() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+        let bincode_config = bincode::config::standard().with_big_endian();
+
+        for (key, value) in [(1u64, "foo"), (3, "bar")] {
+            db.put(
+                cf,
+                bincode::serde::encode_to_vec(key, bincode_config).unwrap(),
+                bincode::serde::encode_to_vec(value, bincode_config).unwrap(),
+            )
+            .unwrap();
+        }
+
+        let corrupt_key = bincode::serde::encode_to_vec(2u64, bincode_config).unwrap();
+        db.put(cf, &corrupt_key, [3, 0xff]).unwrap();
+
+        let results = db
+            .typed_iterator::<u64, String, _>(cf, rocksdb::IteratorMode::Start, bincode_config)
+            .collect::<Vec<_>>();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap(), &(1, "foo".to_string()));
+        assert!(matches!(
+            &results[1],
+            Err(crate::mapper::Error::EntryDecoding { key, .. }) if key.as_ref() == corrupt_key
+        ));
+        assert_eq!(results[2].as_ref().unwrap(), &(3, "bar".to_string()));
+    }
+
+    #[test]
+    fn delete() {
+        let (test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        db.put(cf, b"foo", b"bar").unwrap();
+        db.put(cf, b"baz", b"qux").unwrap();
+        db.delete(cf, b"foo").unwrap();
+
+        assert!(db.get(cf, b"foo").unwrap().is_none());
+        assert!(db.get(cf, b"baz").unwrap().is_some());
+
+        db.close();
+
+        let read_only_db: super::Db = rocksdb::DB::open_cf_for_read_only(
+            &rocksdb::Options::default(),
+            &test_db_dir,
+            ["test"],
+            false,
+        )
+        .unwrap()
+        .into();
+        let cf = read_only_db.handle("test").unwrap();
+
+        assert!(read_only_db.delete(cf, b"baz").is_err());
+        assert!(read_only_db.get(cf, b"baz").unwrap().is_some());
+    }
+
+    #[test]
+    fn write_batch() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        db.write_batch(|batch| {
+            for i in 0..10_000u64 {
+                batch.put_cf(cf, i.to_be_bytes(), (i * 2).to_be_bytes());
+            }
+        })
+        .unwrap();
+
+        for i in 0..10_000u64 {
+            assert_eq!(
+                db.get(cf, i.to_be_bytes()).unwrap().as_deref(),
+                Some(&(i * 2).to_be_bytes()[..])
+            );
+        }
+
+        assert_eq!(
+            db.iterator(cf, rocksdb::IteratorMode::Start).count(),
+            10_000
+        );
+    }
+
+    #[test]
+    fn iterator_from() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        for i in (0..10u64).map(|i| i * 2) {
+            db.put(cf, i.to_be_bytes(), []).unwrap();
+        }
+
+        let keys = |forward| {
+            db.iterator_from(cf, 9u64.to_be_bytes(), forward)
+                .map(|result| {
+                    let (key, _) = result.unwrap();
+
+                    u64::from_be_bytes((*key).try_into().unwrap())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(keys(true), vec![10, 12, 14, 16, 18]);
+        assert_eq!(keys(false), vec![8, 6, 4, 2, 0]);
+        assert_eq!(
+            db.iterator_from(cf, 12u64.to_be_bytes(), false)
+                .map(|result| result.unwrap().0)
+                .next()
+                .as_deref(),
+            Some(&12u64.to_be_bytes()[..])
+        );
+    }
+
+    #[test]
+    fn count() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        assert_eq!(db.count(cf).unwrap(), 0);
+
+        for i in 0..100u64 {
+            db.put(cf, (i % 40).to_be_bytes(), i.to_be_bytes()).unwrap();
+        }
+
+        assert_eq!(db.count(cf).unwrap(), 40);
+        assert!(db.estimate_count(cf).unwrap().is_some());
+    }
+
+    #[test]
+    fn prefix_iterator() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        for key in ["a", "ab:1", "ab:2", "ab:3", "abc", "b:1"] {
+            db.put(cf, key, key).unwrap();
+        }
+
+        let keys = db
+            .prefix_iterator(cf, "ab:")
+            .map(|result| result.map(|(key, _)| key))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            keys,
+            vec![
+                b"ab:1".as_slice().into(),
+                b"ab:2".as_slice().into(),
+                b"ab:3".as_slice().into()
+            ]
+        );
+        assert_eq!(db.prefix_iterator(cf, "c").count(), 0);
+    }
+
+    #[test]
+    fn range_iterator() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        for i in 0..10u64 {
+            db.put(cf, i.to_be_bytes(), []).unwrap();
+        }
+
+        let keys = db
+            .range_iterator(cf, 3u64.to_be_bytes(), 7u64.to_be_bytes())
+            .map(|result| result.map(|(key, _)| u64::from_be_bytes((*key).try_into().unwrap())))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(keys, vec![3, 4, 5, 6]);
+        assert_eq!(
+            db.range_iterator(cf, 5u64.to_be_bytes(), 5u64.to_be_bytes())
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn contains_key() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        db.put(cf, b"foo", b"bar").unwrap();
+
+        assert!(db.key_may_exist(cf, b"foo"));
+        assert!(db.contains_key(cf, b"foo").unwrap());
+        assert!(!db.contains_key(cf, b"baz").unwrap());
+
+        db.delete(cf, b"foo").unwrap();
+
+        assert!(!db.contains_key(cf, b"foo").unwrap());
+    }
+
+    #[test]
+    fn debug() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let db = db.with_cf_names(vec!["test".to_string()]);
+
+        assert_eq!(
+            format!("{db:?}"),
+            r#"Db { mode: "OptimisticTransaction", cf_names: ["test"] }"#
+        );
+    }
+
+    fn check_transaction_delete(db: &super::Db) {
+        let cf = db.handle("test").unwrap();
+
+        db.put(cf, b"old", b"foo").unwrap();
+
+        let tx = db.transaction().unwrap();
+        tx.delete(cf, b"old").unwrap();
+        tx.put(cf, b"new", b"bar").unwrap();
+
+        assert!(db.get(cf, b"old").unwrap().is_some());
+        assert!(db.get(cf, b"new").unwrap().is_none());
+
+        tx.commit().unwrap();
+
+        assert!(db.get(cf, b"old").unwrap().is_none());
+        assert!(db.get(cf, b"new").unwrap().is_some());
+    }
+
+    #[test]
+    fn transaction_delete() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+
+        check_transaction_delete(&db);
+    }
+
+    #[test]
+    fn transaction_delete_with_pessimistic_transactions() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+
+        let db: super::Db = rocksdb::TransactionDB::open_cf(
+            &options,
+            &rocksdb::TransactionDBOptions::default(),
+            &test_db_dir,
+            ["test"],
+        )
+        .unwrap()
+        .into();
+
+        check_transaction_delete(&db);
+    }
+
+    #[test]
+    fn two_phase_commit() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+
+        let db: super::Db = rocksdb::TransactionDB::open_cf(
+            &options,
+            &rocksdb::TransactionDBOptions::default(),
+            &test_db_dir,
+            ["test"],
+        )
+        .unwrap()
+        .into();
+        let cf = db.handle("test").unwrap();
+
+        let tx = db.transaction().unwrap();
+        tx.put(cf, b"foo", b"bar").unwrap();
+
+        // Unnamed transactions cannot be prepared.
+        assert!(matches!(tx.prepare(), Err(crate::error::Error::Db(_))));
+
+        tx.set_name(b"first").unwrap();
+        tx.prepare().unwrap();
+
+        assert!(db.get(cf, b"foo").unwrap().is_none());
+
+        tx.commit().unwrap();
+
+        assert_eq!(db.get(cf, b"foo").unwrap().as_deref(), Some(&b"bar"[..]));
+
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let tx = db.transaction().unwrap();
+
+        assert!(matches!(
+            tx.set_name(b"first"),
+            Err(crate::error::Error::Unsupported)
+        ));
+        assert!(matches!(
+            tx.prepare(),
+            Err(crate::error::Error::Unsupported)
+        ));
+    }
+
+    #[test]
+    fn transaction_with_snapshot() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        // Without a snapshot, a concurrent write before our first write to the key is not a
+        // conflict.
+        let tx = db.transaction().unwrap();
+        db.put(cf, b"foo", b"concurrent").unwrap();
+        tx.put(cf, b"foo", b"ours").unwrap();
+
+        assert!(tx.commit().is_ok());
+
+        // With a snapshot, the same concurrent write is a conflict.
+        let tx = db.transaction_with_snapshot().unwrap();
+        db.put(cf, b"foo", b"concurrent").unwrap();
+        tx.put(cf, b"foo", b"ours").unwrap();
+
+        assert_eq!(tx.commit().unwrap_err().kind(), rocksdb::ErrorKind::Busy);
+        assert_eq!(db.get(cf, b"foo").unwrap().unwrap().as_ref(), b"concurrent");
+    }
+
+    #[test]
+    fn transaction_opt() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        assert!(db
+            .pessimistic_transaction_opt(&rocksdb::TransactionOptions::default())
+            .is_none());
+
+        let tx = db
+            .transaction_opt(&rocksdb::OptimisticTransactionOptions::default())
+            .unwrap();
+        db.put(cf, b"foo", b"concurrent").unwrap();
+        tx.put(cf, b"foo", b"ours").unwrap();
+
+        assert!(tx.commit().is_ok());
+
+        let mut transaction_options = rocksdb::OptimisticTransactionOptions::default();
+        transaction_options.set_snapshot(true);
+
+        let tx = db.transaction_opt(&transaction_options).unwrap();
+        db.put(cf, b"foo", b"concurrent").unwrap();
+        tx.put(cf, b"foo", b"ours").unwrap();
+
+        assert_eq!(tx.commit().unwrap_err().kind(), rocksdb::ErrorKind::Busy);
+    }
+
+    #[test]
+    fn pessimistic_transaction_opt() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+
+        let db: super::Db = rocksdb::TransactionDB::open_cf(
+            &options,
+            &rocksdb::TransactionDBOptions::default(),
+            &test_db_dir,
+            ["test"],
+        )
+        .unwrap()
+        .into();
+        let cf = db.handle("test").unwrap();
+
+        assert!(db
+            .transaction_opt(&rocksdb::OptimisticTransactionOptions::default())
+            .is_none());
+
+        let mut transaction_options = rocksdb::TransactionOptions::default();
+        transaction_options.set_snapshot(true);
+
+        // With a snapshot, the concurrent write is detected when the key is locked.
+        let tx = db
+            .pessimistic_transaction_opt(&transaction_options)
+            .unwrap();
+        db.put(cf, b"foo", b"concurrent").unwrap();
+
+        assert_eq!(
+            tx.put(cf, b"foo", b"ours").unwrap_err().kind(),
+            rocksdb::ErrorKind::Busy
+        );
+    }
+
+    fn check_savepoint(db: &super::Db) {
+        let cf = db.handle("test").unwrap();
+
+        let tx = db.transaction().unwrap();
+        tx.put(cf, b"a", b"foo").unwrap();
+        tx.set_savepoint();
+        tx.put(cf, b"b", b"bar").unwrap();
+        tx.delete(cf, b"a").unwrap();
+        tx.rollback_to_savepoint().unwrap();
+        tx.commit().unwrap();
+
+        assert_eq!(db.get(cf, b"a").unwrap().unwrap().as_ref(), b"foo");
+        assert!(db.get(cf, b"b").unwrap().is_none());
+    }
+
+    #[test]
+    fn savepoint() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+
+        check_savepoint(&db);
+    }
+
+    #[test]
+    fn savepoint_with_write_batch() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+
+        let db =
+            super::Db::read_write(rocksdb::DB::open_cf(&options, &test_db_dir, ["test"]).unwrap());
+
+        check_savepoint(&db);
+    }
+
+    #[test]
+    fn snapshot() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        db.put(cf, b"a", b"old").unwrap();
+
+        let snapshot = db.snapshot();
+
+        db.put(cf, b"a", b"new").unwrap();
+        db.put(cf, b"b", b"new").unwrap();
+
+        assert_eq!(snapshot.get(cf, b"a").unwrap().unwrap().as_ref(), b"old");
+        assert_eq!(
+            snapshot.multi_get(cf, [&b"a"[..], b"b"]).unwrap(),
+            vec![Some(b"old".to_vec()), None]
+        );
+        assert_eq!(
+            snapshot
+                .iterator(cf, rocksdb::IteratorMode::Start)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            vec![(b"a".to_vec().into(), b"old".to_vec().into())]
+        );
+        assert_eq!(db.get(cf, b"a").unwrap().unwrap().as_ref(), b"new");
+    }
+
+    #[test]
+    fn transaction_rollback() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        let tx = db.transaction().unwrap();
+        tx.put(cf, b"foo", b"bar").unwrap();
+        tx.rollback().unwrap();
+
+        assert!(db.get(cf, b"foo").unwrap().is_none());
+    }
+
+    #[test]
+    fn merge_without_merge_operator() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        assert!(matches!(
+            db.merge(cf, b"foo", b"bar"),
+            Err(crate::error::Error::NoMergeOperator)
+        ));
+        assert!(db.get(cf, b"foo").unwrap().is_none());
+    }
+
+    #[test]
+    fn multi_get_typed() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+        let bincode_config = bincode::config::standard();
+
+        db.put(
+            cf,
+            b"a",
+            bincode::serde::encode_to_vec("foo", bincode_config).unwrap(),
+        )
+        .unwrap();
+        db.put(
+            cf,
+            b"b",
+            bincode::serde::encode_to_vec("bar", bincode_config).unwrap(),
+        )
+        .unwrap();
+
+        let values = db
+            .multi_get_typed::<_, _, String, _>(cf, [&b"a"[..], b"c", b"b"], bincode_config)
+            .unwrap();
+
+        assert_eq!(
+            values,
+            vec![Some("foo".to_string()), None, Some("bar".to_string())]
+        );
+
+        // An empty value is not a valid encoding of a string.
+        db.put(cf, b"c", []).unwrap();
+
+        assert!(matches!(
+            db.multi_get_typed::<_, _, String, _>(cf, [&b"a"[..], b"c"], bincode_config),
+            Err(crate::mapper::Error::EntryDecoding { key, .. }) if &*key == b"c"
+        ));
+    }
+
+    #[test]
+    fn typed_keys() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+        let bincode_config = bincode::config::standard();
+
+        for id in [70_000u32, 2, 300, 1] {
+            db.put_typed(
+                cf,
+                &(id, "foo".to_string()),
+                &id.to_string(),
+                bincode_config,
+            )
+            .unwrap();
+        }
+
+        assert_eq!(
+            db.get_typed::<_, String, _>(cf, &(300u32, "foo".to_string()), bincode_config)
+                .unwrap(),
+            Some("300".to_string())
+        );
+        assert!(db
+            .get_typed::<_, String, _>(cf, &(300u32, "bar".to_string()), bincode_config)
+            .unwrap()
+            .is_none());
+
+        let ids = db
+            .iterator(cf, rocksdb::IteratorMode::Start)
+            .map(|result| {
+                let (key, _) = result.unwrap();
+                let ((id, _), _): ((u32, String), _) =
+                    bincode::serde::decode_from_slice(&key, super::KEY_BINCODE_CONFIG).unwrap();
+
+                id
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(ids, vec![1, 2, 300, 70_000]);
+    }
+
+    #[test]
+    fn multi_get_dedup() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        db.put(cf, b"a", b"foo").unwrap();
+        db.put(cf, b"b", b"bar").unwrap();
+
+        let values = db
+            .multi_get_dedup(cf, [&b"a"[..], b"b", b"a", b"c", b"b"])
+            .unwrap();
+
+        assert_eq!(
+            values,
+            vec![
+                Some(b"foo".to_vec()),
+                Some(b"bar".to_vec()),
+                Some(b"foo".to_vec()),
+                None,
+                Some(b"bar".to_vec())
+            ]
+        );
+    }
+
+    #[test]
+    fn as_optimistic() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        db.put(cf, b"foo", b"bar").unwrap();
+
+        let underlying = db.as_optimistic().unwrap();
+
+        assert!(db.as_pessimistic().is_none());
+        assert_eq!(
+            underlying.get_cf(cf, b"foo").unwrap().as_deref(),
+            Some(&b"bar"[..])
+        );
+        assert!(underlying.snapshot().get_cf(cf, b"foo").unwrap().is_some());
+    }
+
+    #[test]
+    fn put_opt() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        // With manual WAL flushes, only synced writes reach the log files before shutdown.
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        options.set_manual_wal_flush(true);
+
+        let db: super::Db =
+            rocksdb::OptimisticTransactionDB::open_cf(&options, &test_db_dir, ["test"])
+                .unwrap()
+                .into();
+        let cf = db.handle("test").unwrap();
+
+        // Copying the files of the open database simulates an abrupt shutdown.
+        let recover = |key: &[u8]| {
+            let copy_dir = tempfile::tempdir().unwrap();
+
+            for entry in std::fs::read_dir(&test_db_dir).unwrap() {
+                let entry = entry.unwrap();
+
+                std::fs::copy(entry.path(), copy_dir.path().join(entry.file_name())).unwrap();
+            }
+
+            let copy =
+                rocksdb::DB::open_cf(&rocksdb::Options::default(), &copy_dir, ["test"]).unwrap();
+            let cf = copy.cf_handle("test").unwrap();
+
+            copy.get_cf(cf, key).unwrap()
+        };
+
+        let mut sync_options = rocksdb::WriteOptions::default();
+        sync_options.set_sync(true);
+
+        let mut no_wal_options = rocksdb::WriteOptions::default();
+        no_wal_options.disable_wal(true);
+
+        db.put_opt(cf, b"unsynced", b"foo", &Default::default())
+            .unwrap();
+
+        assert_eq!(recover(b"unsynced"), None);
+
+        db.put_opt(cf, b"synced", b"bar", &sync_options).unwrap();
+
+        assert_eq!(recover(b"synced").as_deref(), Some(&b"bar"[..]));
+
+        db.put_opt(cf, b"unlogged", b"baz", &no_wal_options)
+            .unwrap();
+        db.sync_wal().unwrap();
+
+        assert_eq!(recover(b"unlogged"), None);
+    }
+
+    #[test]
+    fn sync_wal() {
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        options.set_manual_wal_flush(true);
+
+        let db: super::Db =
+            rocksdb::OptimisticTransactionDB::open_cf(&options, &test_db_dir, ["test"])
+                .unwrap()
+                .into();
+        let cf = db.handle("test").unwrap();
+
+        // Copying the files of the open database simulates an abrupt shutdown.
+        let recover = || {
+            let copy_dir = tempfile::tempdir().unwrap();
+
+            for entry in std::fs::read_dir(&test_db_dir).unwrap() {
+                let entry = entry.unwrap();
+
+                std::fs::copy(entry.path(), copy_dir.path().join(entry.file_name())).unwrap();
+            }
+
+            let copy =
+                rocksdb::DB::open_cf(&rocksdb::Options::default(), &copy_dir, ["test"]).unwrap();
+            let cf = copy.cf_handle("test").unwrap();
+
+            copy.get_cf(cf, b"foo").unwrap()
+        };
+
+        db.put(cf, b"foo", b"bar").unwrap();
+
+        assert_eq!(recover(), None);
+
+        db.sync_wal().unwrap();
+
+        assert_eq!(recover().as_deref(), Some(&b"bar"[..]));
+    }
+
+    #[test]
+    fn get_opt() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        for i in 0..100u64 {
+            db.put(cf, i.to_be_bytes(), i.to_le_bytes()).unwrap();
+        }
+
+        let mut read_options = rocksdb::ReadOptions::default();
+        read_options.fill_cache(false);
+
+        assert_eq!(
+            db.get_opt(cf, 42u64.to_be_bytes(), &read_options)
+                .unwrap()
+                .as_deref(),
+            Some(&42u64.to_le_bytes()[..])
+        );
+        assert!(db
+            .get_opt(cf, 100u64.to_be_bytes(), &read_options)
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            db.multi_get_opt(cf, [1u64, 100, 3].map(u64::to_be_bytes), &read_options)
+                .unwrap(),
+            vec![
+                Some(1u64.to_le_bytes().to_vec()),
+                None,
+                Some(3u64.to_le_bytes().to_vec())
+            ]
+        );
+    }
+
+    #[test]
+    fn key_iterator() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        for i in 0..100u64 {
+            db.put(cf, i.to_be_bytes(), [0; 1024]).unwrap();
+        }
+
+        let start_key = 50u64.to_be_bytes();
+
+        for mode in [
+            rocksdb::IteratorMode::Start,
+            rocksdb::IteratorMode::End,
+            rocksdb::IteratorMode::From(&start_key, rocksdb::Direction::Forward),
+            rocksdb::IteratorMode::From(&start_key, rocksdb::Direction::Reverse),
+        ] {
+            let expected = db
+                .iterator(cf, mode)
+                .map(|result| result.map(|(key, _)| key))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            let keys = db
+                .key_iterator(cf, mode)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+
+            assert!(!keys.is_empty());
+            assert_eq!(keys, expected);
+        }
+    }
+
+    #[test]
+    fn page() {
+        let (_test_db_dir, db) = open_test_db(&["test", "empty"]);
+        let cf = db.handle("test").unwrap();
+
+        for i in 0..10u64 {
+            db.put(cf, i.to_be_bytes(), i.to_le_bytes()).unwrap();
+        }
+
+        let keys = |entries: &[super::KeyValuePair]| {
+            entries
+                .iter()
+                .map(|(key, _)| u64::from_be_bytes(key.as_ref().try_into().unwrap()))
+                .collect::<Vec<_>>()
+        };
+
+        let (first, cursor) = db.page(cf, None, 4).unwrap();
+
+        assert_eq!(keys(&first), vec![0, 1, 2, 3]);
+        assert_eq!(cursor.as_deref(), Some(&3u64.to_be_bytes()[..]));
+
+        let (middle, cursor) = db.page(cf, cursor.as_deref(), 4).unwrap();
+
+        assert_eq!(keys(&middle), vec![4, 5, 6, 7]);
+        assert_eq!(cursor.as_deref(), Some(&7u64.to_be_bytes()[..]));
+
+        // Exactly the limit remains, so there is no next page.
+        let (last, cursor) = db.page(cf, cursor.as_deref(), 2).unwrap();
+
+        assert_eq!(keys(&last), vec![8, 9]);
+        assert_eq!(cursor, None);
+
+        let (last, cursor) = db.page(cf, Some(&7u64.to_be_bytes()), 4).unwrap();
+
+        assert_eq!(keys(&last), vec![8, 9]);
+        assert_eq!(cursor, None);
+
+        assert!(matches!(
+            db.page(cf, None, 0),
+            Err(crate::error::Error::ZeroPageLimit)
+        ));
+        assert!(matches!(
+            db.page(cf, Some(&3u64.to_be_bytes()), 0),
+            Err(crate::error::Error::ZeroPageLimit)
+        ));
+
+        assert_eq!(
+            db.page(db.handle("empty").unwrap(), None, 4).unwrap(),
+            (vec![], None)
+        );
+    }
+
+    #[test]
+    fn close_and_flush() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let db = db.with_cf_names(vec!["test".to_string()]);
+        let cf = db.handle("test").unwrap();
+
+        db.put(cf, b"foo", b"bar").unwrap();
+
+        let active_entries = |db: &super::Db| {
+            db.property_int(
+                db.handle("test").unwrap(),
+                "rocksdb.num-entries-active-mem-table",
+            )
+            .unwrap()
+        };
+
+        assert_eq!(active_entries(&db), Some(1));
+
+        db.clone().close_and_flush().unwrap();
+
+        assert_eq!(active_entries(&db), Some(0));
+        assert_eq!(db.get(cf, b"foo").unwrap().as_deref(), Some(&b"bar"[..]));
+
+        db.put(cf, b"baz", b"qux").unwrap();
+
+        assert_eq!(db.get(cf, b"baz").unwrap().as_deref(), Some(&b"qux"[..]));
+    }
+
+    #[test]
+    fn latest_sequence_number() {
+        let (_test_db_dir, db) = open_test_db(&["test"]);
+        let cf = db.handle("test").unwrap();
+
+        let initial_sequence_number = db.latest_sequence_number().unwrap();
+
+        db.put(cf, b"foo", b"bar").unwrap();
+        db.put(cf, b"qux", b"baz").unwrap();
+
+        assert!(db.latest_sequence_number().unwrap() > initial_sequence_number);
+
+        let test_db_dir = tempfile::tempdir().unwrap();
+
+        let mut options = rocksdb::Options::default();
+        options.create_if_missing(true);
+
+        let db: super::Db = rocksdb::TransactionDB::open(
+            &options,
+            &rocksdb::TransactionDBOptions::default(),
+            &test_db_dir,
+        )
+        .unwrap()
+        .into();
+
+        assert!(matches!(
+            db.latest_sequence_number(),
+            Err(crate::error::Error::Unsupported)
+        ));
+    }
 }